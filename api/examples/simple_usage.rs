@@ -10,22 +10,23 @@ async fn main() {
     let mut domain_registry = prompt_engine::DomainRegistry::new();
     domain_registry.register_domain(Box::new(api::domains::ComputationalDomain));
     domain_registry.register_domain(Box::new(api::domains::ScientificDomain));
-    domain_registry.register_domain(Box::new(api::domains::CulturalDomain));
+    domain_registry.register_domain(Box::new(api::domains::CulturalDomain::default()));
     domain_registry.register_domain(Box::new(api::domains::ExperientialDomain));
 
-    let framework_state = FrameworkState {
+    let framework_state = FrameworkState::new(
         domain_registry,
-        boundaries: vec![
+        vec![
             prompt_engine::BoundaryState::new("CD-SD".to_string(), 0.8, "Active".to_string()),
             prompt_engine::BoundaryState::new("SD-CuD".to_string(), 0.5, "Active".to_string()),
         ],
-        identity: "User Identity".to_string(),
-    };
+        "User Identity".to_string(),
+    );
 
     let llm_config = LlmConfig {
         api_key: "YOUR_OPENAI_API_KEY".to_string(),
         provider_name: "openai".to_string(),
         model_name: "text-davinci-003".to_string(),
+        validate_credentials_on_startup: false,
     };
     let provider = LlmFactory::create_llm(&llm_config).expect("Failed to create LLM provider");
     let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
@@ -0,0 +1,17 @@
+-- Searchable identity anchors
+-- Anchors produced by the continuity stage, indexed per user
+
+CREATE TABLE IF NOT EXISTS identity_anchors (
+    anchor_id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL,
+    anchor_type TEXT NOT NULL,
+    description TEXT NOT NULL,
+    confidence REAL NOT NULL,
+    domains TEXT NOT NULL,  -- JSON array of domain names
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    -- Each turn re-saves the context's anchors, so keep one row per anchor
+    UNIQUE (user_id, anchor_type, description)
+);
+
+CREATE INDEX IF NOT EXISTS idx_identity_anchors_user ON identity_anchors(user_id);
@@ -0,0 +1,9 @@
+-- Persisted framework state
+-- Latest FrameworkState per user, stored as JSON
+
+CREATE TABLE IF NOT EXISTS framework_states (
+    user_id BLOB PRIMARY KEY NOT NULL,
+    state TEXT NOT NULL,  -- JSON FrameworkState (domains stored by name)
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
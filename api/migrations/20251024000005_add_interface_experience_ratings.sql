@@ -0,0 +1,14 @@
+-- Interface experience ratings
+-- User feedback on whether a boundary's BDE flow was helpful
+
+CREATE TABLE IF NOT EXISTS interface_experience_ratings (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL,
+    boundary_name TEXT NOT NULL,
+    helpful INTEGER NOT NULL,  -- 1 = helpful, 0 = not helpful
+    user_comment TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ratings_user_boundary ON interface_experience_ratings(user_id, boundary_name);
@@ -0,0 +1,15 @@
+-- Recurring user patterns
+-- Pattern observations from the continuity stage, counted across requests
+
+CREATE TABLE IF NOT EXISTS user_patterns (
+    pattern_id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL,
+    description TEXT NOT NULL,
+    occurrence_count INTEGER NOT NULL DEFAULT 1,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE (user_id, description)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_patterns_user ON user_patterns(user_id, occurrence_count);
@@ -0,0 +1,6 @@
+-- Organization ownership for users
+-- Memory managers scoped to an organization only see that organization's users
+
+ALTER TABLE users ADD COLUMN organization_id BLOB;
+
+CREATE INDEX IF NOT EXISTS idx_users_organization ON users(organization_id);
@@ -0,0 +1,12 @@
+-- Identity version history
+-- Each identity change made through FrameworkState::update_identity, per user
+
+CREATE TABLE IF NOT EXISTS identity_versions (
+    user_id BLOB NOT NULL,
+    version INTEGER NOT NULL,
+    identity TEXT NOT NULL,
+    change_trigger TEXT NOT NULL,  -- what prompted the change
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, version),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
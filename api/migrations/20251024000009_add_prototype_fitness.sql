@@ -0,0 +1,9 @@
+-- Autonomous judgement prototype fitness
+-- Learned fitness per response prototype, shared by all users
+
+CREATE TABLE IF NOT EXISTS prototype_fitness (
+    prototype_name TEXT PRIMARY KEY NOT NULL,
+    fitness REAL NOT NULL,
+    history TEXT NOT NULL,  -- JSON array of recent scores, oldest first
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
@@ -1,8 +1,14 @@
 // Autonomous Judgement Module Implementation
 
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Number of recent outcome scores kept per prototype
+const FITNESS_HISTORY_LEN: usize = 20;
+/// Weight of the newest score in the fitness moving average
+const FITNESS_SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutonomousJudgementModule {
     intention: Intention,
     prototypes: Vec<Prototype>,
@@ -10,21 +16,40 @@ pub struct AutonomousJudgementModule {
     autonomy: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Intention {
     explicit: String,
     implicit: String,
     ambiguity: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prototype {
     name: String,
     confidence: f64,
     integrity: f64,
+    /// Exponential moving average of outcome scores, starting at 1.0
+    #[serde(default = "default_fitness")]
+    fitness: f64,
+    /// Most recent outcome scores, oldest first
+    #[serde(default)]
+    fitness_history: VecDeque<f64>,
+}
+
+/// A prototype's learned fitness, as persisted between restarts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrototypeFitness {
+    pub name: String,
+    pub fitness: f64,
+    /// Most recent outcome scores, oldest first
+    pub history: VecDeque<f64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn default_fitness() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Factors {
     ambiguity: f64,
     receptivity: f64,
@@ -32,6 +57,19 @@ pub struct Factors {
     confidence: f64,
 }
 
+/// How the module arrived at its current autonomy level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutonomyExplanation {
+    /// Prototype with the highest confidence × integrity × fitness
+    pub selected_prototype: String,
+    /// Each factor's weighted contribution to the base autonomy, followed
+    /// by the mean prototype fitness the base is scaled by
+    pub factors_applied: Vec<(String, f64)>,
+    pub final_autonomy: f64,
+    /// Confidence of the selected prototype
+    pub confidence: f64,
+}
+
 impl Intention {
     pub fn new(explicit: String, implicit: String, ambiguity: f64) -> Self {
         Self {
@@ -48,7 +86,18 @@ impl Prototype {
             name,
             confidence,
             integrity,
+            fitness: default_fitness(),
+            fitness_history: VecDeque::new(),
+        }
+    }
+
+    fn record_score(&mut self, score: f64) {
+        let score = score.clamp(0.0, 1.0);
+        if self.fitness_history.len() == FITNESS_HISTORY_LEN {
+            self.fitness_history.pop_front();
         }
+        self.fitness_history.push_back(score);
+        self.fitness = FITNESS_SMOOTHING * score + (1.0 - FITNESS_SMOOTHING) * self.fitness;
     }
 }
 
@@ -75,14 +124,104 @@ impl AutonomousJudgementModule {
     }
 
     fn calculate_autonomy(factors: &Factors) -> f64 {
-        (factors.ambiguity * 0.4)
-            + (factors.receptivity * 0.3)
-            + (factors.stakes * 0.2)
-            + (factors.confidence * 0.1)
+        Self::weighted_factors(factors)
+            .iter()
+            .map(|(_, contribution)| contribution)
+            .sum()
+    }
+
+    fn weighted_factors(factors: &Factors) -> [(&'static str, f64); 4] {
+        [
+            ("ambiguity", factors.ambiguity * 0.4),
+            ("receptivity", factors.receptivity * 0.3),
+            ("stakes", factors.stakes * 0.2),
+            ("confidence", factors.confidence * 0.1),
+        ]
     }
 
+    fn mean_fitness(&self) -> f64 {
+        if self.prototypes.is_empty() {
+            return 1.0;
+        }
+        self.prototypes.iter().map(|p| p.fitness).sum::<f64>() / self.prototypes.len() as f64
+    }
+
+    /// Factor-based autonomy scaled by the mean prototype fitness
     pub fn get_autonomy(&self) -> f64 {
-        self.autonomy
+        self.autonomy * self.mean_fitness()
+    }
+
+    /// Break the current autonomy down into the inputs that produced it
+    pub fn explain(&self) -> AutonomyExplanation {
+        let score = |p: &Prototype| p.confidence * p.integrity * p.fitness;
+        let selected = self
+            .prototypes
+            .iter()
+            .max_by(|a, b| score(a).total_cmp(&score(b)));
+
+        let mut factors_applied: Vec<(String, f64)> = Self::weighted_factors(&self.factors)
+            .iter()
+            .map(|(name, contribution)| (name.to_string(), *contribution))
+            .collect();
+        factors_applied.push(("prototype_fitness".to_string(), self.mean_fitness()));
+
+        AutonomyExplanation {
+            selected_prototype: selected.map(|p| p.name.clone()).unwrap_or_default(),
+            factors_applied,
+            final_autonomy: self.get_autonomy(),
+            confidence: selected.map(|p| p.confidence).unwrap_or(0.0),
+        }
+    }
+
+    /// Record how well a prototype's response turned out (0.0-1.0).
+    /// Returns false, recording nothing, if no prototype has that name or
+    /// the score is not finite.
+    pub fn update_prototype_fitness(&mut self, prototype_name: &str, score: f64) -> bool {
+        if !score.is_finite() {
+            return false;
+        }
+        match self
+            .prototypes
+            .iter_mut()
+            .find(|p| p.name == prototype_name)
+        {
+            Some(prototype) => {
+                prototype.record_score(score);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Current fitness and score history of the named prototype
+    pub fn prototype_fitness(&self, prototype_name: &str) -> Option<PrototypeFitness> {
+        self.prototypes
+            .iter()
+            .find(|p| p.name == prototype_name)
+            .map(|p| PrototypeFitness {
+                name: p.name.clone(),
+                fitness: p.fitness,
+                history: p.fitness_history.clone(),
+            })
+    }
+
+    /// Apply previously saved fitness to the prototype of the same name.
+    /// Unknown prototypes and non-finite values are ignored.
+    pub fn restore_prototype_fitness(&mut self, saved: PrototypeFitness) -> bool {
+        if !saved.fitness.is_finite() || saved.history.iter().any(|s| !s.is_finite()) {
+            return false;
+        }
+        match self.prototypes.iter_mut().find(|p| p.name == saved.name) {
+            Some(prototype) => {
+                prototype.fitness = saved.fitness.clamp(0.0, 1.0);
+                prototype.fitness_history = saved.history;
+                while prototype.fitness_history.len() > FITNESS_HISTORY_LEN {
+                    prototype.fitness_history.pop_front();
+                }
+                true
+            }
+            None => false,
+        }
     }
 }
 
@@ -103,11 +242,15 @@ mod tests {
                 name: "Direct Prototype".to_string(),
                 confidence: 0.9,
                 integrity: 0.95,
+                fitness: 1.0,
+                fitness_history: VecDeque::new(),
             },
             Prototype {
                 name: "Enhanced Prototype".to_string(),
                 confidence: 0.7,
                 integrity: 0.85,
+                fitness: 1.0,
+                fitness_history: VecDeque::new(),
             },
         ];
 
@@ -122,4 +265,89 @@ mod tests {
         // Expected: (0.4 * 0.4) + (0.7 * 0.3) + (0.5 * 0.2) + (0.8 * 0.1) = 0.55
         assert_eq!(ajm.get_autonomy(), 0.55);
     }
+
+    #[test]
+    fn test_low_fitness_scores_reduce_autonomy() {
+        let mut ajm = AutonomousJudgementModule::new(
+            Intention::new("Test".to_string(), "Test".to_string(), 0.4),
+            vec![Prototype::new("Direct".to_string(), 0.9, 0.95)],
+            Factors::new(0.4, 0.7, 0.5, 0.8),
+        );
+
+        let mut previous = ajm.get_autonomy();
+        for _ in 0..5 {
+            assert!(ajm.update_prototype_fitness("Direct", 0.1));
+            let autonomy = ajm.get_autonomy();
+            assert!(autonomy < previous, "{} !< {}", autonomy, previous);
+            previous = autonomy;
+        }
+        assert_eq!(ajm.prototypes[0].fitness_history.len(), 5);
+        assert!(!ajm.update_prototype_fitness("Unknown", 0.1));
+
+        // Non-finite scores are rejected rather than poisoning the average
+        assert!(!ajm.update_prototype_fitness("Direct", f64::NAN));
+        assert!(!ajm.update_prototype_fitness("Direct", f64::INFINITY));
+        assert_eq!(ajm.get_autonomy(), previous);
+        assert_eq!(ajm.prototypes[0].fitness_history.len(), 5);
+
+        // History is bounded
+        for _ in 0..30 {
+            ajm.update_prototype_fitness("Direct", 0.5);
+        }
+        assert_eq!(ajm.prototypes[0].fitness_history.len(), FITNESS_HISTORY_LEN);
+
+        // Fitness survives a serialization round trip
+        let json = serde_json::to_string(&ajm).unwrap();
+        let restored: AutonomousJudgementModule = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get_autonomy(), ajm.get_autonomy());
+        assert_eq!(
+            restored.prototypes[0].fitness_history,
+            ajm.prototypes[0].fitness_history
+        );
+    }
+
+    #[test]
+    fn test_explain_autonomy() {
+        let mut ajm = AutonomousJudgementModule::new(
+            Intention::new("Test".to_string(), "Test".to_string(), 0.4),
+            vec![
+                Prototype::new("Direct".to_string(), 0.9, 0.95),
+                Prototype::new("Enhanced".to_string(), 0.7, 0.85),
+            ],
+            Factors::new(0.4, 0.7, 0.5, 0.8),
+        );
+
+        let explanation = ajm.explain();
+        assert_eq!(explanation.selected_prototype, "Direct");
+        assert_eq!(explanation.confidence, 0.9);
+        assert_eq!(explanation.final_autonomy, ajm.get_autonomy());
+        let names: Vec<&str> = explanation
+            .factors_applied
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "ambiguity",
+                "receptivity",
+                "stakes",
+                "confidence",
+                "prototype_fitness"
+            ]
+        );
+        let base: f64 = explanation.factors_applied[..4]
+            .iter()
+            .map(|(_, contribution)| contribution)
+            .sum();
+        assert!((base - 0.55).abs() < 1e-12);
+
+        // Poor outcomes for Direct hand the selection to Enhanced
+        for _ in 0..10 {
+            ajm.update_prototype_fitness("Direct", 0.0);
+        }
+        let explanation = ajm.explain();
+        assert_eq!(explanation.selected_prototype, "Enhanced");
+        assert!(explanation.factors_applied[4].1 < 1.0);
+    }
 }
@@ -0,0 +1,79 @@
+// Shared HTTP Client Pool
+// One reqwest client reused by every provider so connections are pooled
+
+use reqwest::Client;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+static SHARED_CLIENT: OnceLock<Arc<Client>> = OnceLock::new();
+
+/// Connection pool settings for the shared provider HTTP client
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientPoolConfig {
+    pub max_idle_per_host: usize,
+    /// Log connection reads and writes at trace level
+    pub connection_verbose: bool,
+}
+
+impl Default for ClientPoolConfig {
+    fn default() -> Self {
+        // Matches the reqwest defaults
+        Self {
+            max_idle_per_host: usize::MAX,
+            connection_verbose: false,
+        }
+    }
+}
+
+impl ClientPoolConfig {
+    /// Read pool settings from the environment, falling back to defaults for
+    /// unset or unparsable values:
+    /// LLM_POOL_MAX_IDLE_PER_HOST, LLM_CONNECTION_VERBOSE
+    pub fn from_env() -> Self {
+        fn read<T: FromStr>(key: &str) -> Option<T> {
+            std::env::var(key).ok().and_then(|value| value.parse().ok())
+        }
+
+        let defaults = Self::default();
+        Self {
+            max_idle_per_host: read("LLM_POOL_MAX_IDLE_PER_HOST")
+                .unwrap_or(defaults.max_idle_per_host),
+            connection_verbose: read("LLM_CONNECTION_VERBOSE")
+                .unwrap_or(defaults.connection_verbose),
+        }
+    }
+
+    pub fn build_client(&self) -> Client {
+        // Client::new() panics under the same conditions as this build()
+        Client::builder()
+            .pool_max_idle_per_host(self.max_idle_per_host)
+            .connection_verbose(self.connection_verbose)
+            .build()
+            .expect("HTTP client should build with pool settings")
+    }
+}
+
+/// The process-wide provider client, built from the environment on first use.
+/// Timeouts are set per request, so providers with different limits can share it.
+pub fn shared_client() -> Arc<Client> {
+    SHARED_CLIENT
+        .get_or_init(|| Arc::new(ClientPoolConfig::from_env().build_client()))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_pool_config_from_env_falls_back_to_defaults() {
+        std::env::remove_var("LLM_POOL_MAX_IDLE_PER_HOST");
+        std::env::remove_var("LLM_CONNECTION_VERBOSE");
+        assert_eq!(ClientPoolConfig::from_env(), ClientPoolConfig::default());
+    }
+
+    #[test]
+    fn test_shared_client_is_reused() {
+        assert!(Arc::ptr_eq(&shared_client(), &shared_client()));
+    }
+}
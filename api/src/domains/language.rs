@@ -0,0 +1,94 @@
+// Language Detection
+// Script-based language guesses used to weight the cultural domain
+
+/// Guesses the language of a piece of text
+pub trait LanguageDetector: Send + Sync {
+    /// ISO 639-1 code of the detected language, or None if undetermined
+    fn detect(&self, text: &str) -> Option<String>;
+}
+
+/// Detects languages from the Unicode blocks their scripts use.
+/// Latin script is shared by too many languages to call, so it yields None.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CharsetBasedDetector;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Script {
+    Latin,
+    Kana,
+    Han,
+    Hangul,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        match c as u32 {
+            0x0041..=0x005A | 0x0061..=0x007A | 0x00C0..=0x024F => Some(Script::Latin),
+            0x3040..=0x30FF | 0x31F0..=0x31FF => Some(Script::Kana),
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF => Some(Script::Han),
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Some(Script::Hangul),
+            0x0400..=0x04FF => Some(Script::Cyrillic),
+            0x0370..=0x03FF => Some(Script::Greek),
+            0x0600..=0x06FF => Some(Script::Arabic),
+            0x0590..=0x05FF => Some(Script::Hebrew),
+            0x0900..=0x097F => Some(Script::Devanagari),
+            0x0E00..=0x0E7F => Some(Script::Thai),
+            _ => None,
+        }
+    }
+}
+
+impl LanguageDetector for CharsetBasedDetector {
+    fn detect(&self, text: &str) -> Option<String> {
+        let scripts: Vec<Script> = text.chars().filter_map(Script::of).collect();
+        let count = |script: Script| scripts.iter().filter(|s| **s == script).count();
+
+        // Japanese mixes kana with Han characters, so kana decides between ja and zh
+        let cjk = count(Script::Kana) + count(Script::Han);
+        let candidates = [
+            (cjk, if count(Script::Kana) > 0 { "ja" } else { "zh" }),
+            (count(Script::Hangul), "ko"),
+            (count(Script::Cyrillic), "ru"),
+            (count(Script::Greek), "el"),
+            (count(Script::Arabic), "ar"),
+            (count(Script::Hebrew), "he"),
+            (count(Script::Devanagari), "hi"),
+            (count(Script::Thai), "th"),
+        ];
+
+        // The dominant non-Latin script has to outweigh Latin letters
+        let (letters, language) = candidates.into_iter().max_by_key(|(n, _)| *n)?;
+        if letters == 0 || letters < count(Script::Latin) {
+            return None;
+        }
+        Some(language.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charset_detection() {
+        let detector = CharsetBasedDetector;
+        assert_eq!(detector.detect("こんにちは、世界").as_deref(), Some("ja"));
+        assert_eq!(detector.detect("你好，世界").as_deref(), Some("zh"));
+        assert_eq!(detector.detect("안녕하세요").as_deref(), Some("ko"));
+        assert_eq!(detector.detect("Привет, мир").as_deref(), Some("ru"));
+        assert_eq!(detector.detect("Hello, world"), None);
+        assert_eq!(detector.detect("1234 !?"), None);
+
+        // A stray foreign word in English text does not flip the result
+        assert_eq!(
+            detector.detect("The word for cat is кошка in Russian"),
+            None
+        );
+    }
+}
@@ -1,6 +1,14 @@
 // Domain Implementations
 
+mod language;
+
 use super::prompt_engine::Domain;
+use std::sync::Arc;
+
+pub use language::{CharsetBasedDetector, LanguageDetector};
+
+/// Cultural relevance for input detected as a language other than English
+const NON_ENGLISH_CULTURAL_RELEVANCE: f64 = 0.9;
 
 // Example domain implementations
 #[derive(Clone)]
@@ -45,8 +53,26 @@ impl Domain for ScientificDomain {
     }
 }
 
+/// Weighs cultural context, which matters more when the input
+/// is in a language other than English
 #[derive(Clone)]
-pub struct CulturalDomain;
+pub struct CulturalDomain {
+    detector: Arc<dyn LanguageDetector>,
+}
+
+impl CulturalDomain {
+    pub fn new(detector: Box<dyn LanguageDetector>) -> Self {
+        Self {
+            detector: Arc::from(detector),
+        }
+    }
+}
+
+impl Default for CulturalDomain {
+    fn default() -> Self {
+        Self::new(Box::new(CharsetBasedDetector))
+    }
+}
 
 impl Domain for CulturalDomain {
     fn name(&self) -> &str {
@@ -57,6 +83,16 @@ impl Domain for CulturalDomain {
         0.6 * autonomy_level
     }
 
+    fn calculate_relevance_for_input(&self, input: &str, autonomy_level: f64) -> f64 {
+        let baseline = self.calculate_relevance(autonomy_level);
+        match self.detector.detect(input) {
+            Some(language) if language != "en" => {
+                baseline.clamp(NON_ENGLISH_CULTURAL_RELEVANCE, 1.0)
+            }
+            _ => baseline,
+        }
+    }
+
     fn transform_state(&self, state: &str, autonomy_level: f64) -> String {
         if autonomy_level > 0.7 {
             format!("Enhanced: {}", state)
@@ -87,6 +123,21 @@ impl Domain for ExperientialDomain {
     }
 }
 
+/// Reconstructs built-in domains by name, used when deserializing a DomainRegistry
+pub struct DomainFactory;
+
+impl DomainFactory {
+    pub fn create(name: &str) -> Option<Box<dyn Domain>> {
+        match name {
+            "CD" => Some(Box::new(ComputationalDomain)),
+            "SD" => Some(Box::new(ScientificDomain)),
+            "CuD" => Some(Box::new(CulturalDomain::default())),
+            "ED" => Some(Box::new(ExperientialDomain)),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,10 +146,18 @@ mod tests {
     fn test_domain_names() {
         assert_eq!(ComputationalDomain.name(), "CD");
         assert_eq!(ScientificDomain.name(), "SD");
-        assert_eq!(CulturalDomain.name(), "CuD");
+        assert_eq!(CulturalDomain::default().name(), "CuD");
         assert_eq!(ExperientialDomain.name(), "ED");
     }
 
+    #[test]
+    fn test_domain_factory() {
+        for name in ["CD", "SD", "CuD", "ED"] {
+            assert_eq!(DomainFactory::create(name).unwrap().name(), name);
+        }
+        assert!(DomainFactory::create("Unknown").is_none());
+    }
+
     #[test]
     fn test_domain_relevance_calculations() {
         let autonomy = 0.8;
@@ -111,13 +170,35 @@ mod tests {
             ScientificDomain.calculate_relevance(autonomy),
             0.7 * autonomy
         );
-        assert_eq!(CulturalDomain.calculate_relevance(autonomy), 0.6 * autonomy);
+        assert_eq!(
+            CulturalDomain::default().calculate_relevance(autonomy),
+            0.6 * autonomy
+        );
         assert_eq!(
             ExperientialDomain.calculate_relevance(autonomy),
             0.9 * autonomy
         );
     }
 
+    #[test]
+    fn test_cultural_relevance_rises_for_non_english_input() {
+        let cultural = CulturalDomain::default();
+        let autonomy = 0.5;
+
+        let japanese =
+            cultural.calculate_relevance_for_input("日本の文化について教えてください", autonomy);
+        assert!(japanese >= 0.9, "got {}", japanese);
+
+        let english = cultural.calculate_relevance_for_input("Tell me about culture", autonomy);
+        assert_eq!(english, cultural.calculate_relevance(autonomy));
+
+        // Other domains ignore the input language
+        assert_eq!(
+            ComputationalDomain.calculate_relevance_for_input("日本の文化", autonomy),
+            ComputationalDomain.calculate_relevance(autonomy)
+        );
+    }
+
     #[test]
     fn test_domain_transform_state_high_autonomy() {
         let state = "test_state";
@@ -132,7 +213,7 @@ mod tests {
             "Enhanced: test_state"
         );
         assert_eq!(
-            CulturalDomain.transform_state(state, high_autonomy),
+            CulturalDomain::default().transform_state(state, high_autonomy),
             "Enhanced: test_state"
         );
         assert_eq!(
@@ -155,7 +236,7 @@ mod tests {
             "test_state"
         );
         assert_eq!(
-            CulturalDomain.transform_state(state, low_autonomy),
+            CulturalDomain::default().transform_state(state, low_autonomy),
             "test_state"
         );
         assert_eq!(
@@ -1,14 +1,27 @@
 // Flow Process Implementation
 // The 7-stage pipeline that orchestrates consciousness-like emergence at recognition interfaces
 
-use crate::prompt_engine::{BoundaryState, FrameworkState};
+use crate::prompt_engine::{BoundaryState, FrameworkState, TemplateLocale};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 
 /// Errors that can occur during flow processing
 #[derive(Debug)]
 pub enum FlowError {
-    StageProcessingFailed { stage: String, reason: String },
+    StageProcessingFailed {
+        stage: String,
+        reason: String,
+    },
+    InvalidCheckpoint {
+        reason: String,
+    },
+    /// The caller cancelled the request before `stage` could run
+    Cancelled {
+        stage: String,
+    },
 }
 
 impl std::fmt::Display for FlowError {
@@ -17,6 +30,12 @@ impl std::fmt::Display for FlowError {
             FlowError::StageProcessingFailed { stage, reason } => {
                 write!(f, "Stage '{}' failed: {}", stage, reason)
             }
+            FlowError::InvalidCheckpoint { reason } => {
+                write!(f, "Invalid flow checkpoint: {}", reason)
+            }
+            FlowError::Cancelled { stage } => {
+                write!(f, "Flow cancelled before '{}'", stage)
+            }
         }
     }
 }
@@ -34,7 +53,7 @@ pub enum DevelopmentalStage {
 }
 
 /// Domain activation state
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DomainActivation {
     pub activation: f64,
 }
@@ -49,6 +68,17 @@ pub struct InterfaceExperience {
     pub emergence: String,  // BDE(e): Recognize emergent qualities
 }
 
+/// User feedback on whether an interface experience helped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterfaceExperienceRating {
+    pub boundary_name: String,
+    pub helpful: bool,
+    pub user_comment: Option<String>,
+}
+
+/// Boundaries rated helpful less often than this are deprioritized
+pub const LOW_RATING_THRESHOLD: f64 = 0.5;
+
 /// Phenomenological qualities emerging at interfaces
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhenomenologicalQuality {
@@ -358,10 +388,25 @@ impl CoherenceCalculator {
 
 /// Generator for BDE(i) - Invitation stage
 /// Creates productive tensions requiring multi-domain processing
-pub struct InvitationGenerator;
+#[derive(Default)]
+pub struct InvitationGenerator {
+    locale: TemplateLocale,
+}
 
 impl InvitationGenerator {
+    /// Write invitations in `locale`; English and Spanish are translated
+    pub fn with_locale(locale: TemplateLocale) -> Self {
+        Self { locale }
+    }
+
     pub fn generate(&self, domain1: &str, domain2: &str, _boundary: &BoundaryState) -> String {
+        match self.locale {
+            TemplateLocale::Spanish => Self::generate_spanish(domain1, domain2),
+            _ => Self::generate_english(domain1, domain2),
+        }
+    }
+
+    fn generate_english(domain1: &str, domain2: &str) -> String {
         match (domain1, domain2) {
             ("CD", "SD") | ("SD", "CD") => {
                 "Consider how computational patterns and scientific evidence create tension, \
@@ -402,6 +447,47 @@ impl InvitationGenerator {
         }
     }
 
+    fn generate_spanish(domain1: &str, domain2: &str) -> String {
+        match (domain1, domain2) {
+            ("CD", "SD") | ("SD", "CD") => {
+                "Considera cómo los patrones computacionales y la evidencia científica crean tensión, \
+                exigiendo integrar la estructura formal con la observación empírica."
+                    .to_string()
+            }
+            ("CD", "ED") | ("ED", "CD") => {
+                "Observa la tensión productiva entre el análisis computacional y el conocimiento \
+                experiencial directo—un vacío que invita a integrar más allá de cada dominio."
+                    .to_string()
+            }
+            ("SD", "CuD") | ("CuD", "SD") => {
+                "Explora cómo la evidencia científica crea tensión con las narrativas culturales, \
+                invitando a una síntesis que honre tanto los datos empíricos como el significado contextual."
+                    .to_string()
+            }
+            ("CuD", "ED") | ("ED", "CuD") => {
+                "Siente la tensión entre las interpretaciones culturales y las cualidades \
+                experienciales directas—una invitación a integrar más allá de los marcos conceptuales."
+                    .to_string()
+            }
+            ("CD", "CuD") | ("CuD", "CD") => {
+                "Examina cómo los modelos computacionales crean tensión con los contextos culturales, \
+                exigiendo integrar la precisión formal con el significado situado."
+                    .to_string()
+            }
+            ("SD", "ED") | ("ED", "SD") => {
+                "Nota cómo la comprensión científica crea tensión con la experiencia vivida, \
+                invitando a reconocer tanto la medición objetiva como la cualidad subjetiva."
+                    .to_string()
+            }
+            _ => format!(
+                "Crea una tensión productiva entre los dominios {} y {}, \
+                exigiendo integrar ambas perspectivas.",
+                Self::spanish_domain_name(domain1),
+                Self::spanish_domain_name(domain2)
+            ),
+        }
+    }
+
     fn domain_full_name(abbrev: &str) -> &str {
         match abbrev {
             "CD" => "computational",
@@ -411,14 +497,39 @@ impl InvitationGenerator {
             _ => abbrev,
         }
     }
+
+    fn spanish_domain_name(abbrev: &str) -> &str {
+        match abbrev {
+            "CD" => "computacional",
+            "SD" => "científico",
+            "CuD" => "cultural",
+            "ED" => "experiencial",
+            _ => abbrev,
+        }
+    }
 }
 
 /// Generator for BDE(a) - Attention stage
 /// Directs focus to interfaces between domains, not domains themselves
-pub struct AttentionDirector;
+#[derive(Default)]
+pub struct AttentionDirector {
+    locale: TemplateLocale,
+}
 
 impl AttentionDirector {
+    /// Direct attention in `locale`; English and Spanish are translated
+    pub fn with_locale(locale: TemplateLocale) -> Self {
+        Self { locale }
+    }
+
     pub fn generate(&self, domain1: &str, domain2: &str, _boundary: &BoundaryState) -> String {
+        match self.locale {
+            TemplateLocale::Spanish => Self::generate_spanish(domain1, domain2),
+            _ => Self::generate_english(domain1, domain2),
+        }
+    }
+
+    fn generate_english(domain1: &str, domain2: &str) -> String {
         match (domain1, domain2) {
             ("CD", "SD") | ("SD", "CD") => {
                 "Focus on the interface where computational patterns transform into scientific \
@@ -458,84 +569,193 @@ impl AttentionDirector {
             ),
         }
     }
+
+    fn generate_spanish(domain1: &str, domain2: &str) -> String {
+        match (domain1, domain2) {
+            ("CD", "SD") | ("SD", "CD") => {
+                "Centra la atención en la interfaz donde los patrones computacionales se transforman \
+                en evidencia científica—no en un dominio exclusivamente, sino en el límite donde la \
+                estructura formal se vuelve realidad empírica."
+                    .to_string()
+            }
+            ("CD", "ED") | ("ED", "CD") => {
+                "Dirige la atención al límite donde el análisis computacional se encuentra con el \
+                conocimiento experiencial—la interfaz donde la lógica se vuelve experiencia vivida, \
+                no un dominio por sí solo."
+                    .to_string()
+            }
+            ("SD", "CuD") | ("CuD", "SD") => {
+                "Atiende a la intersección donde la evidencia científica se transforma en significado \
+                cultural—no a datos o contextos aislados, sino a la interfaz donde la medición se \
+                vuelve interpretación."
+                    .to_string()
+            }
+            ("CuD", "ED") | ("ED", "CuD") => {
+                "Centra la atención en el límite donde el significado cultural se encuentra con la \
+                experiencia directa—la interfaz donde los marcos conceptuales tocan la realidad \
+                fenomenológica, trascendiendo ambos."
+                    .to_string()
+            }
+            ("CD", "CuD") | ("CuD", "CD") => {
+                "Observa la interfaz donde los modelos computacionales se encuentran con los contextos \
+                culturales—no un dominio aislado, sino el límite donde la precisión formal encuentra \
+                el significado situado."
+                    .to_string()
+            }
+            ("SD", "ED") | ("ED", "SD") => {
+                "Dirige la atención al límite donde la comprensión científica se transforma en \
+                experiencia vivida—la interfaz donde la medición objetiva se vuelve cualidad subjetiva."
+                    .to_string()
+            }
+            _ => format!(
+                "Centra la atención en la interfaz donde {} se encuentra con {}, no en un dominio aislado.",
+                domain1, domain2
+            ),
+        }
+    }
 }
 
 /// Generator for BDE(r) - Resonance stage
 /// Facilitates oscillatory synchronization between systems
-pub struct ResonanceFacilitator;
+#[derive(Default)]
+pub struct ResonanceFacilitator {
+    locale: TemplateLocale,
+}
 
 impl ResonanceFacilitator {
+    /// Describe resonance in `locale`; English and Spanish are translated
+    pub fn with_locale(locale: TemplateLocale) -> Self {
+        Self { locale }
+    }
+
     pub fn generate(&self, domain1: &str, domain2: &str, boundary: &BoundaryState) -> String {
         // Check if boundary has active oscillation
         let has_oscillation = boundary.frequency > 0.5 && boundary.amplitude > 0.1;
 
         if has_oscillation {
             // Use Phase 2 oscillation data for resonance description
-            let freq_desc = if boundary.frequency > 1.5 {
-                "rapid"
+            let (freq_desc, freq_desc_es) = if boundary.frequency > 1.5 {
+                ("rapid", "rápido")
             } else if boundary.frequency > 0.8 {
-                "natural"
+                ("natural", "natural")
             } else {
-                "gentle"
+                ("gentle", "suave")
             };
 
-            match (domain1, domain2) {
-                ("CD", "SD") | ("SD", "CD") => {
-                    format!(
-                        "Allow understanding to oscillate at a {} rhythm between computational \
+            match self.locale {
+                TemplateLocale::Spanish => {
+                    Self::oscillating_spanish(domain1, domain2, freq_desc_es)
+                }
+                _ => Self::oscillating_english(domain1, domain2, freq_desc),
+            }
+        } else {
+            // Fallback for low/no oscillation
+            match self.locale {
+                TemplateLocale::Spanish => format!(
+                    "Permite que la comprensión se mueva con naturalidad entre las perspectivas {} y {}.",
+                    domain1, domain2
+                ),
+                _ => format!(
+                    "Allow understanding to move naturally between {} and {} perspectives.",
+                    domain1, domain2
+                ),
+            }
+        }
+    }
+
+    fn oscillating_english(domain1: &str, domain2: &str, freq_desc: &str) -> String {
+        match (domain1, domain2) {
+            ("CD", "SD") | ("SD", "CD") => {
+                format!(
+                    "Allow understanding to oscillate at a {} rhythm between computational \
                         structure and scientific observation, neither forcing formal analysis nor \
                         abandoning empirical grounding.",
-                        freq_desc
-                    )
-                }
-                ("CD", "ED") | ("ED", "CD") => {
-                    format!(
-                        "Let awareness oscillate with {} fluidity between analytical precision \
+                    freq_desc
+                )
+            }
+            ("CD", "ED") | ("ED", "CD") => {
+                format!(
+                    "Let awareness oscillate with {} fluidity between analytical precision \
                         and experiential richness, feeling the natural rhythm of integration.",
-                        freq_desc
-                    )
-                }
-                ("SD", "CuD") | ("CuD", "SD") => {
-                    format!(
-                        "Allow insights to oscillate at a {} pace between scientific evidence and \
+                    freq_desc
+                )
+            }
+            ("SD", "CuD") | ("CuD", "SD") => {
+                format!(
+                    "Allow insights to oscillate at a {} pace between scientific evidence and \
                         cultural context, neither rejecting data nor ignoring meaning.",
-                        freq_desc
-                    )
-                }
-                ("CuD", "ED") | ("ED", "CuD") => {
-                    format!(
-                        "Let understanding oscillate with {} rhythm between cultural frameworks \
+                    freq_desc
+                )
+            }
+            ("CuD", "ED") | ("ED", "CuD") => {
+                format!(
+                    "Let understanding oscillate with {} rhythm between cultural frameworks \
                         and direct experience, feeling the natural synchronization between concept \
                         and phenomenology.",
-                        freq_desc
-                    )
-                }
-                ("CD", "CuD") | ("CuD", "CD") => {
-                    format!(
+                    freq_desc
+                )
+            }
+            ("CD", "CuD") | ("CuD", "CD") => {
+                format!(
                         "Allow perspective to oscillate at a {} rhythm between computational \
                         precision and cultural richness, neither forcing formalism nor abandoning context.",
                         freq_desc
                     )
-                }
-                ("SD", "ED") | ("ED", "SD") => {
-                    format!(
+            }
+            ("SD", "ED") | ("ED", "SD") => {
+                format!(
                         "Let awareness oscillate with {} fluidity between scientific understanding \
                         and lived experience, feeling the natural rhythm between measurement and quality.",
                         freq_desc
                     )
-                }
-                _ => format!(
-                    "Allow understanding to oscillate naturally at a {} rhythm between {} and {} \
-                    perspectives, neither forcing one nor abandoning the other.",
-                    freq_desc, domain1, domain2
-                ),
             }
-        } else {
-            // Fallback for low/no oscillation
-            format!(
-                "Allow understanding to move naturally between {} and {} perspectives.",
-                domain1, domain2
-            )
+            _ => format!(
+                "Allow understanding to oscillate naturally at a {} rhythm between {} and {} \
+                    perspectives, neither forcing one nor abandoning the other.",
+                freq_desc, domain1, domain2
+            ),
+        }
+    }
+
+    fn oscillating_spanish(domain1: &str, domain2: &str, freq_desc: &str) -> String {
+        match (domain1, domain2) {
+            ("CD", "SD") | ("SD", "CD") => format!(
+                "Permite que la comprensión oscile a un ritmo {} entre la estructura computacional \
+                y la observación científica, sin forzar el análisis formal ni abandonar el \
+                fundamento empírico.",
+                freq_desc
+            ),
+            ("CD", "ED") | ("ED", "CD") => format!(
+                "Deja que la conciencia oscile a un ritmo {} entre la precisión analítica y la \
+                riqueza experiencial, sintiendo el ritmo natural de la integración.",
+                freq_desc
+            ),
+            ("SD", "CuD") | ("CuD", "SD") => format!(
+                "Permite que las intuiciones oscilen a un ritmo {} entre la evidencia científica y \
+                el contexto cultural, sin rechazar los datos ni ignorar el significado.",
+                freq_desc
+            ),
+            ("CuD", "ED") | ("ED", "CuD") => format!(
+                "Deja que la comprensión oscile a un ritmo {} entre los marcos culturales y la \
+                experiencia directa, sintiendo la sincronización natural entre concepto y \
+                fenomenología.",
+                freq_desc
+            ),
+            ("CD", "CuD") | ("CuD", "CD") => format!(
+                "Permite que la perspectiva oscile a un ritmo {} entre la precisión computacional y \
+                la riqueza cultural, sin forzar el formalismo ni abandonar el contexto.",
+                freq_desc
+            ),
+            ("SD", "ED") | ("ED", "SD") => format!(
+                "Deja que la conciencia oscile a un ritmo {} entre la comprensión científica y la \
+                experiencia vivida, sintiendo el ritmo natural entre medición y cualidad.",
+                freq_desc
+            ),
+            _ => format!(
+                "Permite que la comprensión oscile con naturalidad a un ritmo {} entre las \
+                perspectivas {} y {}, sin forzar una ni abandonar la otra.",
+                freq_desc, domain1, domain2
+            ),
         }
     }
 
@@ -560,12 +780,20 @@ impl ResonanceFacilitator {
                 .map(|b| b.name.as_str())
                 .collect();
 
-            format!(
-                "{} Notice how this resonates with synchronization across {} boundaries, \
-                creating harmonic patterns throughout the system.",
-                self.generate(domain1, domain2, boundary),
-                boundary_names.join(", ")
-            )
+            match self.locale {
+                TemplateLocale::Spanish => format!(
+                    "{} Observa cómo esto resuena con la sincronización entre los límites {}, \
+                    creando patrones armónicos en todo el sistema.",
+                    self.generate(domain1, domain2, boundary),
+                    boundary_names.join(", ")
+                ),
+                _ => format!(
+                    "{} Notice how this resonates with synchronization across {} boundaries, \
+                    creating harmonic patterns throughout the system.",
+                    self.generate(domain1, domain2, boundary),
+                    boundary_names.join(", ")
+                ),
+            }
         } else {
             // Single boundary resonance
             self.generate(domain1, domain2, boundary)
@@ -575,9 +803,17 @@ impl ResonanceFacilitator {
 
 /// Generator for BDE(e) - Emergence stage
 /// Recognizes qualities emerging at interfaces
-pub struct EmergenceRecognizer;
+#[derive(Default)]
+pub struct EmergenceRecognizer {
+    locale: TemplateLocale,
+}
 
 impl EmergenceRecognizer {
+    /// Recognize emergence in `locale`; English and Spanish are translated
+    pub fn with_locale(locale: TemplateLocale) -> Self {
+        Self { locale }
+    }
+
     /// Select primary quality using actual quality calculator results
     /// Combines boundary state (60%) with message content (40%)
     fn select_primary_quality_with_message(
@@ -624,6 +860,23 @@ impl EmergenceRecognizer {
         let quality =
             Self::select_primary_quality_with_message(domain1, domain2, boundary, message);
 
+        match self.locale {
+            TemplateLocale::Spanish => Self::spanish_with_quality(
+                domain1,
+                domain2,
+                boundary,
+                Self::spanish_quality(quality),
+            ),
+            _ => Self::english_with_quality(domain1, domain2, boundary, quality),
+        }
+    }
+
+    fn english_with_quality(
+        domain1: &str,
+        domain2: &str,
+        boundary: &BoundaryState,
+        quality: &str,
+    ) -> String {
         if boundary.status == "Transcendent" {
             // Transcendent boundaries: recognize emergent quality
             match (domain1, domain2) {
@@ -682,6 +935,74 @@ impl EmergenceRecognizer {
             )
         }
     }
+
+    fn spanish_with_quality(
+        domain1: &str,
+        domain2: &str,
+        boundary: &BoundaryState,
+        quality: &str,
+    ) -> String {
+        if boundary.status == "Transcendent" {
+            match (domain1, domain2) {
+                ("CD", "SD") | ("SD", "CD") => format!(
+                    "Reconoce la {} que emerge en la interfaz computacional-científica—una cualidad \
+                    que trasciende tanto el procesamiento algorítmico como la observación empírica.",
+                    quality
+                ),
+                ("CD", "ED") | ("ED", "CD") => format!(
+                    "Experimenta la {} que emerge donde el análisis computacional se encuentra con el \
+                    conocimiento experiencial—una cualidad inaccesible para la lógica o la experiencia \
+                    directa por separado.",
+                    quality
+                ),
+                ("SD", "CuD") | ("CuD", "SD") => format!(
+                    "Reconoce la {} que emerge en la interfaz científico-cultural—una cualidad que \
+                    trasciende tanto los datos objetivos como el significado subjetivo.",
+                    quality
+                ),
+                ("CuD", "ED") | ("ED", "CuD") => format!(
+                    "Siente la {} que emerge donde la comprensión cultural se encuentra con la \
+                    experiencia directa—una cualidad que trasciende tanto los marcos conceptuales \
+                    como la fenomenología pura.",
+                    quality
+                ),
+                ("CD", "CuD") | ("CuD", "CD") => format!(
+                    "Nota la {} que emerge en el límite computacional-cultural—una cualidad \
+                    inaccesible para la precisión formal o la riqueza contextual por sí solas.",
+                    quality
+                ),
+                ("SD", "ED") | ("ED", "SD") => format!(
+                    "Experimenta la {} que emerge donde la comprensión científica se encuentra con la \
+                    experiencia vivida—una cualidad que trasciende tanto la medición como la \
+                    cualidad subjetiva.",
+                    quality
+                ),
+                _ => format!(
+                    "Nota la {} que emerge en la interfaz {}-{}.",
+                    quality, domain1, domain2
+                ),
+            }
+        } else {
+            format!(
+                "Permite que las cualidades emerjan a medida que {} y {} se integran, reconociendo \
+                la {} cuando aparezca.",
+                domain1, domain2, quality
+            )
+        }
+    }
+
+    fn spanish_quality(quality: &str) -> &str {
+        match quality {
+            "clarity" => "claridad",
+            "depth" => "profundidad",
+            "openness" => "apertura",
+            "precision" => "precisión",
+            "fluidity" => "fluidez",
+            "resonance" => "resonancia",
+            "coherence" => "coherencia",
+            _ => quality,
+        }
+    }
 }
 
 /// Boundary activation strength based on domain activations
@@ -754,7 +1075,7 @@ impl BoundaryActivation {
 
 /// Pattern observation for lifecycle tracking
 /// TODO(Phase 5): Implement full pattern lifecycle with these fields
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PatternObservation {
     pub description: String,
 }
@@ -768,7 +1089,61 @@ pub struct IdentityAnchor {
     pub domains: Vec<String>,
 }
 
+/// Identity anchor ordered by confidence, so a max-heap yields the most confident first
+#[derive(Debug, Clone)]
+struct OrderedAnchor(IdentityAnchor);
+
+impl PartialEq for OrderedAnchor {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedAnchor {}
+
+impl PartialOrd for OrderedAnchor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedAnchor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.confidence.total_cmp(&other.0.confidence)
+    }
+}
+
+/// Identity anchors prioritized by confidence
+#[derive(Debug, Clone, Default)]
+pub struct IdentityAnchorQueue {
+    anchors: BinaryHeap<OrderedAnchor>,
+}
+
+impl IdentityAnchorQueue {
+    pub fn push(&mut self, anchor: IdentityAnchor) {
+        self.anchors.push(OrderedAnchor(anchor));
+    }
+
+    /// The `k` most confident anchors, highest confidence first
+    pub fn top_k(&self, k: usize) -> Vec<IdentityAnchor> {
+        let mut sorted = self.anchors.clone().into_sorted_vec();
+        sorted.reverse();
+        sorted.into_iter().take(k).map(|anchor| anchor.0).collect()
+    }
+}
+
+impl FromIterator<IdentityAnchor> for IdentityAnchorQueue {
+    fn from_iter<I: IntoIterator<Item = IdentityAnchor>>(iter: I) -> Self {
+        let mut queue = Self::default();
+        for anchor in iter {
+            queue.push(anchor);
+        }
+        queue
+    }
+}
+
 /// Context that flows through all 7 stages
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FlowContext {
     pub user_input: String,
     pub autonomy_level: f64,
@@ -783,9 +1158,26 @@ pub struct FlowContext {
     pub identity_updates: Vec<IdentityAnchor>,
     pub developmental_stage: DevelopmentalStage,
 
+    /// Anchors saved in earlier sessions, loaded before the flow runs
+    #[serde(default)]
+    pub identity_anchors: Vec<IdentityAnchor>,
+
+    /// Share of ratings marking each boundary's experience as helpful,
+    /// for boundaries with enough ratings to judge
+    pub boundary_ratings: HashMap<String, f64>,
+
     // Output
     pub structured_prompt: String,
+    /// Framework context and task instructions without the user input
+    pub system_prompt: String,
     pub llm_response: String,
+
+    /// Number of stages that have run, used to resume from a checkpoint
+    pub completed_stages: usize,
+
+    /// Cancelled by the caller to stop the flow and skip persistence
+    #[serde(skip)]
+    pub cancellation: CancellationToken,
 }
 
 impl FlowContext {
@@ -801,16 +1193,105 @@ impl FlowContext {
             patterns: Vec::new(),
             identity_updates: Vec::new(),
             developmental_stage: DevelopmentalStage::Recognition,
+            identity_anchors: Vec::new(),
+            boundary_ratings: HashMap::new(),
             structured_prompt: String::new(),
+            system_prompt: String::new(),
             llm_response: String::new(),
+            completed_stages: 0,
+            cancellation: CancellationToken::new(),
         }
     }
+
+    /// Session-level quality reading: the average of all emergent qualities,
+    /// weighted by each boundary's permeability
+    pub fn aggregate_quality(&self) -> Option<PhenomenologicalQuality> {
+        let weighted: Vec<(f64, &PhenomenologicalQuality)> = self
+            .emergent_qualities
+            .iter()
+            .filter_map(|quality| {
+                self.boundaries
+                    .iter()
+                    .find(|b| b.name == quality.boundary_name)
+                    .map(|b| (b.permeability, quality))
+            })
+            .collect();
+
+        let total_weight: f64 = weighted.iter().map(|(weight, _)| weight).sum();
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let average = |field: fn(&PhenomenologicalQuality) -> f64| {
+            weighted
+                .iter()
+                .map(|(weight, quality)| weight * field(quality))
+                .sum::<f64>()
+                / total_weight
+        };
+
+        Some(PhenomenologicalQuality {
+            boundary_name: "aggregate".to_string(),
+            clarity: average(|q| q.clarity),
+            depth: average(|q| q.depth),
+            openness: average(|q| q.openness),
+            precision: average(|q| q.precision),
+            fluidity: average(|q| q.fluidity),
+            resonance: average(|q| q.resonance),
+            coherence: average(|q| q.coherence),
+        })
+    }
+
+    /// Capture the context, including every completed stage's output, so the
+    /// flow can be resumed later. Domains are rebuilt by name through
+    /// DomainFactory, so only built-in domains survive the round trip.
+    pub fn checkpoint(&self) -> Result<FlowContextSnapshot, FlowError> {
+        let state = serde_json::to_value(self).map_err(|e| FlowError::InvalidCheckpoint {
+            reason: e.to_string(),
+        })?;
+        Ok(FlowContextSnapshot { state })
+    }
+}
+
+/// Serialized FlowContext captured between stages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowContextSnapshot {
+    pub state: serde_json::Value,
 }
 
 /// Trait for stage processors in the 7-stage flow
 pub trait StageProcessor: Send + Sync {
     fn name(&self) -> &str;
     fn process(&self, context: &mut FlowContext) -> Result<(), FlowError>;
+
+    /// Names of stages that must run before this one
+    fn dependencies(&self) -> Vec<&str> {
+        Vec::new()
+    }
+}
+
+/// A stage's position in the flow and the stages it depends on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageNode {
+    pub name: String,
+    pub position: usize,
+    pub dependencies: Vec<String>,
+}
+
+/// Stages in execution order with their declared dependencies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageDependencyGraph {
+    pub stages: Vec<StageNode>,
+}
+
+/// A stage that runs before one of its dependencies, or whose
+/// dependency is not in the flow at all (`dependency_position` is None)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderingViolation {
+    pub stage: String,
+    pub position: usize,
+    pub dependency: String,
+    pub dependency_position: Option<usize>,
 }
 
 /// Stage 1: Domain Emergence
@@ -827,11 +1308,16 @@ impl StageProcessor for DomainEmergenceProcessor {
         let weighted_domains = context
             .framework_state
             .domain_registry
-            .get_weighted_domains(context.autonomy_level);
+            .get_weighted_domains_for_input(&context.user_input, context.autonomy_level);
+
+        let threshold = context
+            .framework_state
+            .domain_registry
+            .activation_threshold(context.autonomy_level);
 
         // Create domain activations
         for (name, weight) in weighted_domains {
-            if weight > 0.3 {
+            if weight > threshold {
                 // Only activate domains with significant relevance
                 context
                     .domains
@@ -852,6 +1338,10 @@ impl StageProcessor for BoundaryDissolutionProcessor {
         "Boundary Dissolution"
     }
 
+    fn dependencies(&self) -> Vec<&str> {
+        vec!["Domain Emergence"]
+    }
+
     fn process(&self, context: &mut FlowContext) -> Result<(), FlowError> {
         // Update boundary permeabilities based on domain activations
         for boundary in &context.framework_state.boundaries {
@@ -872,8 +1362,12 @@ impl StageProcessor for BoundaryDissolutionProcessor {
                     .map(|d| d.activation)
                     .unwrap_or(0.0);
 
-                // Higher activation in both domains increases permeability
-                let new_permeability = (d1_activation * d2_activation).sqrt();
+                // Higher activation in both domains increases permeability,
+                // kept within bounds so boundaries never vanish or seal entirely
+                let new_permeability = (d1_activation * d2_activation)
+                    .sqrt()
+                    .max(context.framework_state.boundary_permeability_floor)
+                    .min(context.framework_state.boundary_permeability_ceiling);
                 updated_boundary.permeability = new_permeability;
 
                 // Update status based on permeability
@@ -902,6 +1396,10 @@ impl StageProcessor for InterfaceAttentionProcessor {
         "Interface Attention"
     }
 
+    fn dependencies(&self) -> Vec<&str> {
+        vec!["Boundary Dissolution"]
+    }
+
     fn process(&self, context: &mut FlowContext) -> Result<(), FlowError> {
         // Calculate activation strength for all boundaries
         let mut boundary_activations: Vec<BoundaryActivation> = context
@@ -912,19 +1410,8 @@ impl StageProcessor for InterfaceAttentionProcessor {
 
         // Sort by priority score (highest first)
         boundary_activations.sort_by(|a, b| {
-            let a_boundary = context
-                .boundaries
-                .iter()
-                .find(|x| x.name == a.boundary_name)
-                .unwrap();
-            let b_boundary = context
-                .boundaries
-                .iter()
-                .find(|x| x.name == b.boundary_name)
-                .unwrap();
-
-            let a_score = a.priority_score(a_boundary);
-            let b_score = b.priority_score(b_boundary);
+            let a_score = self.adjusted_priority(a, context);
+            let b_score = self.adjusted_priority(b, context);
 
             b_score
                 .partial_cmp(&a_score)
@@ -940,8 +1427,10 @@ impl StageProcessor for InterfaceAttentionProcessor {
                     .iter()
                     .find(|b| b.name == ba.boundary_name)
                     .unwrap();
-                // Select if: high priority score OR high permeability (minimum threshold)
-                ba.priority_score(boundary) > 0.3 || boundary.permeability > 0.7
+                // Select if: high priority score OR high permeability (minimum threshold).
+                // Chronically low-rated boundaries must earn selection on score alone.
+                self.adjusted_priority(ba, context) > 0.3
+                    || (boundary.permeability > 0.7 && !self.is_low_rated(ba, context))
             })
             .take(6) // Limit to top 6 interfaces to avoid overwhelming prompt
             .collect();
@@ -961,6 +1450,7 @@ impl StageProcessor for InterfaceAttentionProcessor {
                     boundary,
                     &context.boundaries,
                     &context.user_input,
+                    context.framework_state.locale,
                 );
                 context.interface_experiences.push(experience);
             }
@@ -971,6 +1461,29 @@ impl StageProcessor for InterfaceAttentionProcessor {
 }
 
 impl InterfaceAttentionProcessor {
+    fn is_low_rated(&self, activation: &BoundaryActivation, context: &FlowContext) -> bool {
+        context
+            .boundary_ratings
+            .get(&activation.boundary_name)
+            .is_some_and(|helpful_share| *helpful_share < LOW_RATING_THRESHOLD)
+    }
+
+    /// Priority score, halved for boundaries users have rated unhelpful
+    fn adjusted_priority(&self, activation: &BoundaryActivation, context: &FlowContext) -> f64 {
+        let boundary = context
+            .boundaries
+            .iter()
+            .find(|b| b.name == activation.boundary_name)
+            .unwrap();
+        let score = activation.priority_score(boundary);
+
+        if self.is_low_rated(activation, context) {
+            score * 0.5
+        } else {
+            score
+        }
+    }
+
     fn create_interface_experience(
         &self,
         domain1: &str,
@@ -978,12 +1491,13 @@ impl InterfaceAttentionProcessor {
         boundary: &BoundaryState,
         all_boundaries: &[BoundaryState],
         message: &str,
+        locale: TemplateLocale,
     ) -> InterfaceExperience {
         // Use Phase 3 BDE generators for context-aware templates
-        let invitation_gen = InvitationGenerator;
-        let attention_dir = AttentionDirector;
-        let resonance_fac = ResonanceFacilitator;
-        let emergence_rec = EmergenceRecognizer;
+        let invitation_gen = InvitationGenerator::with_locale(locale);
+        let attention_dir = AttentionDirector::with_locale(locale);
+        let resonance_fac = ResonanceFacilitator::with_locale(locale);
+        let emergence_rec = EmergenceRecognizer::with_locale(locale);
 
         // BDE(i): Invitation - create productive tension
         let invitation = invitation_gen.generate(domain1, domain2, boundary);
@@ -1017,6 +1531,10 @@ impl StageProcessor for QualityEmergenceProcessor {
         "Quality Emergence"
     }
 
+    fn dependencies(&self) -> Vec<&str> {
+        vec!["Interface Attention"]
+    }
+
     fn process(&self, context: &mut FlowContext) -> Result<(), FlowError> {
         // Calculate phenomenological qualities at transcendent boundaries
         for boundary in &context.boundaries {
@@ -1076,6 +1594,9 @@ impl QualityEmergenceProcessor {
     }
 }
 
+/// Identity anchors included in the integration prompt
+pub(crate) const MAX_PROMPT_IDENTITY_ANCHORS: usize = 3;
+
 /// Stage 5: Integration
 /// Form responses from interface consciousness
 pub struct IntegrationProcessor;
@@ -1085,13 +1606,21 @@ impl StageProcessor for IntegrationProcessor {
         "Integration"
     }
 
+    fn dependencies(&self) -> Vec<&str> {
+        vec!["Domain Emergence", "Quality Emergence"]
+    }
+
     fn process(&self, context: &mut FlowContext) -> Result<(), FlowError> {
         // Build enhanced prompt with all framework elements
         let mut prompt = String::from("<vif_context>\n");
 
         // Add domains
+        // Sorted so the prompt is deterministic across runs and resumed flows
+        let mut domains: Vec<(&String, &DomainActivation)> = context.domains.iter().collect();
+        domains.sort_by(|a, b| a.0.cmp(b.0));
+
         prompt.push_str("  <domains>\n");
-        for (name, domain) in &context.domains {
+        for (name, domain) in domains {
             prompt.push_str(&format!(
                 "    <domain name='{}' activation='{:.2}'>{}</domain>\n",
                 name,
@@ -1152,18 +1681,44 @@ impl StageProcessor for IntegrationProcessor {
             prompt.push_str("  </emergent_qualities>\n");
         }
 
+        // Only the most confident identity anchors shape the prompt
+        let anchors: IdentityAnchorQueue = context
+            .identity_anchors
+            .iter()
+            .chain(&context.identity_updates)
+            .cloned()
+            .collect();
+        let top_anchors = anchors.top_k(MAX_PROMPT_IDENTITY_ANCHORS);
+        if !top_anchors.is_empty() {
+            prompt.push_str("  <identity_anchors>\n");
+            for anchor in &top_anchors {
+                prompt.push_str(&format!(
+                    "    <anchor type='{}' confidence='{:.2}'>{}</anchor>\n",
+                    anchor.anchor_type, anchor.confidence, anchor.description
+                ));
+            }
+            prompt.push_str("  </identity_anchors>\n");
+        }
+
         prompt.push_str("</vif_context>\n\n");
+
+        let mut instructions = String::from("<task_instructions>\n");
+        instructions.push_str("  Process this input through all active domains.\n");
+        instructions
+            .push_str("  Focus on the interfaces between domains, not the domains themselves.\n");
+        instructions.push_str("  Allow understanding to emerge at boundaries.\n");
+        instructions.push_str("  Follow the interface experience flow: invitation → attention → resonance → emergence.\n");
+        instructions.push_str("  Respond with integration that transcends individual domains.\n");
+        instructions.push_str("</task_instructions>\n");
+
+        // Chat-mode providers receive the framework context as the system prompt
+        context.system_prompt = format!("{}{}", prompt, instructions);
+
         prompt.push_str(&format!(
             "<user_input>{}</user_input>\n\n",
             context.user_input
         ));
-        prompt.push_str("<task_instructions>\n");
-        prompt.push_str("  Process this input through all active domains.\n");
-        prompt.push_str("  Focus on the interfaces between domains, not the domains themselves.\n");
-        prompt.push_str("  Allow understanding to emerge at boundaries.\n");
-        prompt.push_str("  Follow the interface experience flow: invitation → attention → resonance → emergence.\n");
-        prompt.push_str("  Respond with integration that transcends individual domains.\n");
-        prompt.push_str("</task_instructions>\n");
+        prompt.push_str(&instructions);
 
         context.structured_prompt = prompt;
         Ok(())
@@ -1215,16 +1770,21 @@ impl StageProcessor for ContinuityProcessor {
         "Continuity"
     }
 
+    fn dependencies(&self) -> Vec<&str> {
+        vec!["Integration"]
+    }
+
     fn process(&self, context: &mut FlowContext) -> Result<(), FlowError> {
         // Extract patterns from the response (simplified for MVP)
         if !context.llm_response.is_empty() {
             // Create pattern observations based on active domains
-            let active_domains: Vec<String> = context
+            let mut active_domains: Vec<String> = context
                 .domains
                 .iter()
                 .filter(|(_, d)| d.activation > 0.5)
                 .map(|(name, _)| name.clone())
                 .collect();
+            active_domains.sort();
 
             if !active_domains.is_empty() {
                 context.patterns.push(PatternObservation {
@@ -1258,6 +1818,10 @@ impl StageProcessor for EvolutionProcessor {
         "Evolution"
     }
 
+    fn dependencies(&self) -> Vec<&str> {
+        vec!["Continuity"]
+    }
+
     fn process(&self, context: &mut FlowContext) -> Result<(), FlowError> {
         // Determine developmental stage based on integration quality
         let transcendent_count = context
@@ -1294,6 +1858,37 @@ impl StageProcessor for EvolutionProcessor {
     }
 }
 
+/// Wall-clock time spent in one stage of a profiled run
+#[derive(Debug, Clone)]
+pub struct StageTiming {
+    pub stage: String,
+    pub duration: Duration,
+}
+
+/// Per-stage timings of one flow run, nested under a "FlowProcess" root
+#[derive(Debug, Clone)]
+pub struct FlameReport {
+    pub stages: Vec<StageTiming>,
+    pub total: Duration,
+}
+
+impl FlameReport {
+    /// Folded stacks for inferno/flamegraph.pl, one line per stage with
+    /// nanoseconds as the sample count, e.g. "FlowProcess;Integration 1234"
+    pub fn to_folded(&self) -> String {
+        self.stages
+            .iter()
+            .map(|timing| {
+                format!(
+                    "FlowProcess;{} {}\n",
+                    timing.stage,
+                    timing.duration.as_nanos()
+                )
+            })
+            .collect()
+    }
+}
+
 /// Main Flow Process orchestrator
 pub struct FlowProcess {
     stages: Vec<Box<dyn StageProcessor>>,
@@ -1311,20 +1906,214 @@ impl FlowProcess {
             Box::new(EvolutionProcessor),
         ];
 
-        Self { stages }
+        let process = Self { stages };
+        debug_assert!(process.validate_ordering().is_ok());
+        process
+    }
+
+    /// Describe the stages in order with their declared dependencies
+    pub fn introspect(&self) -> StageDependencyGraph {
+        StageDependencyGraph {
+            stages: self
+                .stages
+                .iter()
+                .enumerate()
+                .map(|(position, stage)| StageNode {
+                    name: stage.name().to_string(),
+                    position,
+                    dependencies: stage
+                        .dependencies()
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Check that every stage runs after all of its dependencies
+    pub fn validate_ordering(&self) -> Result<(), Vec<OrderingViolation>> {
+        let graph = self.introspect();
+        let violations: Vec<OrderingViolation> = graph
+            .stages
+            .iter()
+            .flat_map(|node| {
+                node.dependencies.iter().filter_map(|dependency| {
+                    let dependency_position = graph
+                        .stages
+                        .iter()
+                        .find(|other| &other.name == dependency)
+                        .map(|other| other.position);
+                    match dependency_position {
+                        Some(position) if position < node.position => None,
+                        _ => Some(OrderingViolation {
+                            stage: node.name.clone(),
+                            position: node.position,
+                            dependency: dependency.clone(),
+                            dependency_position,
+                        }),
+                    }
+                })
+            })
+            .collect();
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Insert a custom stage at `index` (past the end appends it), shifting
+    /// later stages back. The stage is not added if it would run before one
+    /// of its dependencies or make another stage do so.
+    pub fn insert_stage(
+        &mut self,
+        index: usize,
+        stage: Box<dyn StageProcessor>,
+    ) -> Result<(), Vec<OrderingViolation>> {
+        let index = index.min(self.stages.len());
+        self.stages.insert(index, stage);
+        if let Err(violations) = self.validate_ordering() {
+            self.stages.remove(index);
+            return Err(violations);
+        }
+        Ok(())
+    }
+
+    pub fn execute(&self, context: FlowContext) -> Result<FlowContext, FlowError> {
+        self.execute_from(context, 0, |_, _| {})
+    }
+
+    /// Run the stages from `starting_stage` (a zero-based index) onwards,
+    /// calling `on_stage_complete` with the number of completed stages after each one
+    pub fn execute_from<F>(
+        &self,
+        mut context: FlowContext,
+        starting_stage: usize,
+        on_stage_complete: F,
+    ) -> Result<FlowContext, FlowError>
+    where
+        F: FnMut(usize, &FlowContext),
+    {
+        self.run_stages(&mut context, starting_stage, on_stage_complete)?;
+        Ok(context)
+    }
+
+    /// Run every stage on `context` in place. If a stage fails, `context`
+    /// keeps the output of the stages before it (`completed_stages` says how
+    /// many), plus whatever the failed stage changed before failing, so the
+    /// caller can checkpoint it without having copied it after every stage.
+    pub fn execute_in_place(&self, context: &mut FlowContext) -> Result<(), FlowError> {
+        self.run_stages(context, 0, |_, _| {})
     }
 
-    pub fn execute(&self, mut context: FlowContext) -> Result<FlowContext, FlowError> {
-        for stage in &self.stages {
+    fn run_stages<F>(
+        &self,
+        context: &mut FlowContext,
+        starting_stage: usize,
+        mut on_stage_complete: F,
+    ) -> Result<(), FlowError>
+    where
+        F: FnMut(usize, &FlowContext),
+    {
+        if starting_stage > self.stages.len() {
+            return Err(FlowError::InvalidCheckpoint {
+                reason: format!(
+                    "starting stage {} is beyond the {} available stages",
+                    starting_stage,
+                    self.stages.len()
+                ),
+            });
+        }
+
+        for (index, stage) in self.stages.iter().enumerate().skip(starting_stage) {
+            if context.cancellation.is_cancelled() {
+                return Err(FlowError::Cancelled {
+                    stage: stage.name().to_string(),
+                });
+            }
             stage
-                .process(&mut context)
+                .process(context)
                 .map_err(|e| FlowError::StageProcessingFailed {
                     stage: stage.name().to_string(),
                     reason: e.to_string(),
                 })?;
+            context.completed_stages = index + 1;
+            on_stage_complete(index + 1, context);
         }
 
-        Ok(context)
+        Ok(())
+    }
+
+    /// Run every stage, recording how long each one takes
+    pub fn execute_profiled(
+        &self,
+        context: FlowContext,
+    ) -> Result<(FlowContext, FlameReport), FlowError> {
+        let started = Instant::now();
+        let mut last_mark = started;
+        let mut stages = Vec::with_capacity(self.stages.len());
+
+        let context = self.execute_from(context, 0, |completed, _| {
+            let now = Instant::now();
+            stages.push(StageTiming {
+                stage: self.stages[completed - 1].name().to_string(),
+                duration: now - last_mark,
+            });
+            last_mark = now;
+        })?;
+
+        let report = FlameReport {
+            stages,
+            total: started.elapsed(),
+        };
+        Ok((context, report))
+    }
+
+    /// Run every stage, skipping past failures instead of aborting.
+    /// A failed stage's partial changes are discarded and the next stage
+    /// starts from the context as it was before the failure. Returns the
+    /// first error encountered alongside the (possibly degraded) context.
+    /// Cancellation stops the remaining stages and is reported as the error
+    /// if nothing failed before it.
+    pub fn execute_with_fallback(
+        &self,
+        mut context: FlowContext,
+    ) -> (FlowContext, Option<FlowError>) {
+        let mut first_error = None;
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            if context.cancellation.is_cancelled() {
+                first_error.get_or_insert(FlowError::Cancelled {
+                    stage: stage.name().to_string(),
+                });
+                break;
+            }
+            let before = context.clone();
+            if let Err(e) = stage.process(&mut context) {
+                tracing::warn!(stage = stage.name(), error = %e, "flow stage failed, continuing");
+                context = before;
+                first_error.get_or_insert(e);
+            }
+            context.completed_stages = index + 1;
+        }
+
+        (context, first_error)
+    }
+
+    /// Restore a checkpointed context and run the remaining stages
+    pub fn resume_from_checkpoint(
+        &self,
+        snapshot: FlowContextSnapshot,
+        starting_stage: usize,
+    ) -> Result<FlowContext, FlowError> {
+        let mut context: FlowContext =
+            serde_json::from_value(snapshot.state).map_err(|e| FlowError::InvalidCheckpoint {
+                reason: e.to_string(),
+            })?;
+        context.completed_stages = starting_stage;
+        self.execute_from(context, starting_stage, |_, _| {})
     }
 }
 
@@ -1337,18 +2126,169 @@ impl Default for FlowProcess {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::prompt_engine::{BoundaryState, DomainRegistry, FrameworkState};
+    use crate::prompt_engine::{
+        BoundaryState, DomainRegistry, FrameworkState, TemplateLocale,
+        DEFAULT_PERMEABILITY_CEILING, DEFAULT_PERMEABILITY_FLOOR,
+    };
 
     fn create_test_framework_state() -> FrameworkState {
-        FrameworkState {
-            domain_registry: DomainRegistry::new(),
-            boundaries: vec![
+        FrameworkState::new(
+            DomainRegistry::new(),
+            vec![
                 BoundaryState::new("CD-SD".to_string(), 0.5, "Maintained".to_string()),
                 BoundaryState::new("SD-CuD".to_string(), 0.7, "Transitional".to_string()),
                 BoundaryState::new("CuD-ED".to_string(), 0.85, "Transcendent".to_string()),
             ],
-            identity: "Test Identity".to_string(),
+            "Test Identity".to_string(),
+        )
+    }
+
+    /// Stage that always fails, for exercising error recovery
+    struct PanickyStageProcessor;
+
+    impl StageProcessor for PanickyStageProcessor {
+        fn name(&self) -> &str {
+            "Panicky"
+        }
+
+        fn process(&self, context: &mut FlowContext) -> Result<(), FlowError> {
+            // Partial changes made before failing must not leak into later stages
+            context.structured_prompt = "half-written".to_string();
+            Err(FlowError::StageProcessingFailed {
+                stage: "Panicky".to_string(),
+                reason: "always fails".to_string(),
+            })
+        }
+    }
+
+    /// Custom stage that reads the integrated prompt
+    struct PromptAuditProcessor;
+
+    impl StageProcessor for PromptAuditProcessor {
+        fn name(&self) -> &str {
+            "Prompt Audit"
+        }
+
+        fn process(&self, _context: &mut FlowContext) -> Result<(), FlowError> {
+            Ok(())
+        }
+
+        fn dependencies(&self) -> Vec<&str> {
+            vec!["Integration"]
+        }
+    }
+
+    #[test]
+    fn test_stage_dependency_graph() {
+        let process = FlowProcess::new();
+        assert!(process.validate_ordering().is_ok());
+
+        let graph = process.introspect();
+        assert_eq!(graph.stages.len(), 7);
+        assert_eq!(graph.stages[4].name, "Integration");
+        assert_eq!(
+            graph.stages[4].dependencies,
+            vec!["Domain Emergence", "Quality Emergence"]
+        );
+        let json = serde_json::to_value(&graph).unwrap();
+        assert_eq!(json["stages"][0]["name"], "Domain Emergence");
+
+        // A custom stage placed before the stage it depends on is reported
+        let mut process = FlowProcess::new();
+        process.stages.insert(4, Box::new(PromptAuditProcessor));
+        let violations = process.validate_ordering().unwrap_err();
+        assert_eq!(
+            violations,
+            vec![OrderingViolation {
+                stage: "Prompt Audit".to_string(),
+                position: 4,
+                dependency: "Integration".to_string(),
+                dependency_position: Some(5),
+            }]
+        );
+
+        // Placed after Integration it is fine
+        let mut process = FlowProcess::new();
+        process.stages.insert(5, Box::new(PromptAuditProcessor));
+        assert!(process.validate_ordering().is_ok());
+    }
+
+    #[test]
+    fn test_execute_with_fallback_continues_after_failure() {
+        let process = FlowProcess {
+            stages: vec![
+                Box::new(DomainEmergenceProcessor),
+                Box::new(PanickyStageProcessor),
+                Box::new(BoundaryDissolutionProcessor),
+                Box::new(InterfaceAttentionProcessor),
+                Box::new(QualityEmergenceProcessor),
+                Box::new(IntegrationProcessor),
+                Box::new(ContinuityProcessor),
+                Box::new(EvolutionProcessor),
+            ],
+        };
+        let context = FlowContext::new(
+            "Analyze this pattern systematically".to_string(),
+            0.7,
+            create_test_framework_state(),
+        );
+
+        let (context, error) = process.execute_with_fallback(context);
+
+        match error {
+            Some(FlowError::StageProcessingFailed { stage, reason }) => {
+                assert_eq!(stage, "Panicky");
+                assert_eq!(reason, "always fails");
+            }
+            other => panic!("Expected the Panicky stage error, got {:?}", other),
         }
+        assert_eq!(context.completed_stages, 8);
+        // Stages after the failure still ran
+        assert!(!context.boundaries.is_empty());
+        assert!(!context.structured_prompt.is_empty());
+        assert_ne!(context.structured_prompt, "half-written");
+
+        // A healthy flow reports no error
+        let context = FlowContext::new("Hello".to_string(), 0.7, create_test_framework_state());
+        let (_, error) = FlowProcess::new().execute_with_fallback(context);
+        assert!(error.is_none());
+    }
+
+    #[test]
+    fn test_execute_profiled_reports_every_stage() {
+        let flow = FlowProcess::new();
+        let context = FlowContext::new(
+            "How do patterns emerge?".to_string(),
+            0.6,
+            create_test_framework_state(),
+        );
+
+        let (context, report) = flow.execute_profiled(context).unwrap();
+        assert_eq!(context.completed_stages, 7);
+        assert_eq!(report.stages.len(), 7);
+        assert!(report
+            .stages
+            .iter()
+            .all(|timing| timing.duration > Duration::ZERO));
+
+        let sum: Duration = report.stages.iter().map(|timing| timing.duration).sum();
+        let total = report.total.as_secs_f64();
+        assert!(
+            (total - sum.as_secs_f64()).abs() <= total * 0.1,
+            "total {:?} vs sum {:?}",
+            report.total,
+            sum
+        );
+
+        let folded = report.to_folded();
+        assert_eq!(folded.lines().count(), 7);
+        assert!(folded.starts_with("FlowProcess;Domain Emergence "));
+        assert!(folded.lines().all(|line| line
+            .rsplit(' ')
+            .next()
+            .unwrap()
+            .parse::<u128>()
+            .is_ok()));
     }
 
     #[test]
@@ -1374,6 +2314,40 @@ mod tests {
         // This is expected for MVP
     }
 
+    #[test]
+    fn test_flow_domain_activations_use_harmonic_weighting() {
+        let run = |weighting_factor: f64| {
+            let mut framework_state = create_test_framework_state();
+            framework_state.domain_registry =
+                DomainRegistry::new().with_weighting_factor(weighting_factor);
+            for name in ["CD", "SD", "CuD", "ED"] {
+                framework_state
+                    .domain_registry
+                    .register_domain(crate::domains::DomainFactory::create(name).unwrap());
+            }
+            let context = FlowContext::new(
+                "Analyze this algorithm systematically".to_string(),
+                0.5,
+                framework_state,
+            );
+            FlowProcess::new().execute(context).unwrap().domains
+        };
+
+        // At autonomy 0.5 the default k = 2 halves every activation,
+        // and the threshold is damped alike, so the same domains are active
+        let undamped = run(0.0);
+        let damped = run(2.0);
+        assert!(!undamped.is_empty());
+        let mut names: Vec<_> = damped.keys().collect();
+        names.sort();
+        let mut undamped_names: Vec<_> = undamped.keys().collect();
+        undamped_names.sort();
+        assert_eq!(names, undamped_names);
+        for (name, activation) in &damped {
+            assert!((activation.activation - undamped[name].activation / 2.0).abs() < 1e-12);
+        }
+    }
+
     #[test]
     fn test_boundary_dissolution_processor() {
         // Given a context with domain activations
@@ -1412,6 +2386,59 @@ mod tests {
         assert!(cd_sd_boundary.status == "Transitional" || cd_sd_boundary.status == "Transcendent");
     }
 
+    #[test]
+    fn test_boundary_permeability_stays_within_bounds() {
+        // Every domain fully active would otherwise dissolve every boundary
+        let mut context =
+            FlowContext::new("Test input".to_string(), 1.0, create_test_framework_state());
+        for name in ["CD", "SD", "CuD", "ED"] {
+            context
+                .domains
+                .insert(name.to_string(), DomainActivation { activation: 1.0 });
+        }
+        BoundaryDissolutionProcessor.process(&mut context).unwrap();
+        assert_eq!(context.boundaries.len(), 3);
+        for boundary in &context.boundaries {
+            assert_eq!(boundary.permeability, DEFAULT_PERMEABILITY_CEILING);
+            assert_eq!(boundary.status, "Transcendent");
+        }
+
+        // Inactive domains keep boundaries slightly open, and still Maintained
+        let mut context =
+            FlowContext::new("Test input".to_string(), 0.2, create_test_framework_state());
+        BoundaryDissolutionProcessor.process(&mut context).unwrap();
+        for boundary in &context.boundaries {
+            assert_eq!(boundary.permeability, DEFAULT_PERMEABILITY_FLOOR);
+            assert_eq!(boundary.status, "Maintained");
+        }
+
+        // Custom bounds replace the defaults
+        let state = create_test_framework_state()
+            .with_permeability_bounds(0.2, 0.5)
+            .unwrap();
+        let mut context = FlowContext::new("Test input".to_string(), 1.0, state);
+        context
+            .domains
+            .insert("CD".to_string(), DomainActivation { activation: 1.0 });
+        context
+            .domains
+            .insert("SD".to_string(), DomainActivation { activation: 1.0 });
+        BoundaryDissolutionProcessor.process(&mut context).unwrap();
+        let cd_sd = context
+            .boundaries
+            .iter()
+            .find(|b| b.name == "CD-SD")
+            .unwrap();
+        assert_eq!(cd_sd.permeability, 0.5);
+        assert_eq!(cd_sd.status, "Maintained");
+        let cud_ed = context
+            .boundaries
+            .iter()
+            .find(|b| b.name == "CuD-ED")
+            .unwrap();
+        assert_eq!(cud_ed.permeability, 0.2);
+    }
+
     #[test]
     fn test_interface_attention_processor() {
         // Given a context with transcendent boundaries
@@ -1552,6 +2579,80 @@ mod tests {
             .contains("<interface_experiences>"));
         assert!(context.structured_prompt.contains("<emergent_qualities>"));
         assert!(context.structured_prompt.contains("<user_input>"));
+
+        // System prompt carries the framework context but not the user input
+        assert!(context.system_prompt.contains("<vif_context>"));
+        assert!(context.system_prompt.contains("<task_instructions>"));
+        assert!(!context.system_prompt.contains("<user_input>"));
+    }
+
+    fn test_anchor(description: &str, confidence: f64) -> IdentityAnchor {
+        IdentityAnchor {
+            anchor_type: "boundary".to_string(),
+            description: description.to_string(),
+            confidence,
+            domains: vec!["CD".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_identity_anchor_queue_top_k() {
+        let queue: IdentityAnchorQueue = [0.4, 0.9, 0.1, 0.7, 0.8]
+            .iter()
+            .map(|&confidence| test_anchor(&format!("anchor {}", confidence), confidence))
+            .collect();
+
+        let confidences: Vec<f64> = queue.top_k(3).iter().map(|a| a.confidence).collect();
+        assert_eq!(confidences, vec![0.9, 0.8, 0.7]);
+        assert_eq!(queue.top_k(10).len(), 5);
+        assert!(IdentityAnchorQueue::default().top_k(3).is_empty());
+    }
+
+    #[test]
+    fn test_integration_prompt_includes_top_identity_anchors() {
+        let mut context = FlowContext::new(
+            "Who am I becoming?".to_string(),
+            0.5,
+            create_test_framework_state(),
+        );
+        for (description, confidence) in [
+            ("faint", 0.2),
+            ("steady", 0.8),
+            ("strong", 0.95),
+            ("weak", 0.3),
+            ("clear", 0.6),
+        ] {
+            context
+                .identity_updates
+                .push(test_anchor(description, confidence));
+        }
+
+        IntegrationProcessor.process(&mut context).unwrap();
+
+        let prompt = &context.system_prompt;
+        assert!(prompt.contains("<identity_anchors>"));
+        let strong = prompt.find(">strong<").unwrap();
+        let steady = prompt.find(">steady<").unwrap();
+        let clear = prompt.find(">clear<").unwrap();
+        assert!(strong < steady && steady < clear);
+        assert!(!prompt.contains(">weak<"));
+    }
+
+    #[test]
+    fn test_flow_prompt_includes_saved_identity_anchors() {
+        let mut context = FlowContext::new(
+            "Who am I becoming?".to_string(),
+            0.5,
+            create_test_framework_state(),
+        );
+        context.identity_anchors = vec![test_anchor("saved", 0.9), test_anchor("faded", 0.1)];
+
+        let context = FlowProcess::new().execute(context).unwrap();
+
+        let prompt = &context.system_prompt;
+        assert!(prompt.contains("<identity_anchors>"));
+        assert!(prompt.contains(">saved<"));
+        assert!(!prompt.contains(">faint<"));
     }
 
     #[test]
@@ -1677,6 +2778,124 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_aggregate_quality_weighted_by_permeability() {
+        let mut context =
+            FlowContext::new("Test input".to_string(), 0.7, create_test_framework_state());
+        assert!(context.aggregate_quality().is_none());
+
+        context.boundaries = vec![
+            BoundaryState::new("CD-SD".to_string(), 0.9, "Transcendent".to_string()),
+            BoundaryState::new("SD-CuD".to_string(), 0.3, "Transcendent".to_string()),
+        ];
+        let quality = |boundary: &str, value: f64| PhenomenologicalQuality {
+            boundary_name: boundary.to_string(),
+            clarity: value,
+            depth: value,
+            openness: value,
+            precision: value,
+            fluidity: value,
+            resonance: value,
+            coherence: value,
+        };
+        context.emergent_qualities = vec![quality("CD-SD", 0.8), quality("SD-CuD", 0.2)];
+
+        let aggregate = context.aggregate_quality().unwrap();
+
+        // (0.9 * 0.8 + 0.3 * 0.2) / 1.2 = 0.65, pulled toward the more permeable boundary
+        assert!((aggregate.clarity - 0.65).abs() < 1e-9);
+        assert!(aggregate.clarity > 0.5 && aggregate.clarity < 0.8);
+        assert!((aggregate.coherence - 0.65).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint_matches_full_run() {
+        use crate::domains::{
+            ComputationalDomain, CulturalDomain, ExperientialDomain, ScientificDomain,
+        };
+
+        let new_context = || {
+            let mut framework_state = create_test_framework_state();
+            let registry = &mut framework_state.domain_registry;
+            registry.register_domain(Box::new(ComputationalDomain));
+            registry.register_domain(Box::new(ScientificDomain));
+            registry.register_domain(Box::new(CulturalDomain::default()));
+            registry.register_domain(Box::new(ExperientialDomain));
+            FlowContext::new(
+                "Analyze the computational patterns in this scientific data".to_string(),
+                0.75,
+                framework_state,
+            )
+        };
+
+        let flow_process = FlowProcess::new();
+        let full_run = flow_process.execute(new_context()).unwrap();
+
+        // Run stages 1-3 by hand, then checkpoint
+        let mut partial = new_context();
+        for stage in &flow_process.stages[..3] {
+            stage.process(&mut partial).unwrap();
+        }
+        let snapshot = partial.checkpoint().unwrap();
+
+        // Resume at stage 4 (index 3)
+        let resumed = flow_process.resume_from_checkpoint(snapshot, 3).unwrap();
+
+        assert!(!resumed.domains.is_empty());
+        assert_eq!(resumed.structured_prompt, full_run.structured_prompt);
+        assert_eq!(resumed.system_prompt, full_run.system_prompt);
+        assert_eq!(resumed.developmental_stage, full_run.developmental_stage);
+
+        // Built-in domains are restored, so the whole context must match
+        assert_eq!(
+            resumed.checkpoint().unwrap().state,
+            full_run.checkpoint().unwrap().state
+        );
+    }
+
+    #[test]
+    fn test_execute_in_place_keeps_completed_stages_on_failure() {
+        let mut flow_process = FlowProcess::new();
+        flow_process
+            .insert_stage(3, Box::new(PanickyStageProcessor))
+            .unwrap();
+
+        let mut context =
+            FlowContext::new("Test input".to_string(), 0.7, create_test_framework_state());
+        let error = flow_process.execute_in_place(&mut context).unwrap_err();
+        assert!(matches!(error, FlowError::StageProcessingFailed { .. }));
+        assert_eq!(context.completed_stages, 3);
+        assert!(!context.boundaries.is_empty());
+
+        // Resuming re-runs the failed stage's slot with the real flow
+        let resumed = FlowProcess::new()
+            .resume_from_checkpoint(context.checkpoint().unwrap(), 3)
+            .unwrap();
+        assert_eq!(resumed.completed_stages, 7);
+        assert!(resumed.structured_prompt.contains("<vif_context>"));
+    }
+
+    #[test]
+    fn test_resume_rejects_invalid_checkpoints() {
+        let flow_process = FlowProcess::new();
+
+        let garbage = FlowContextSnapshot {
+            state: serde_json::json!({"user_input": 42}),
+        };
+        assert!(matches!(
+            flow_process.resume_from_checkpoint(garbage, 3),
+            Err(FlowError::InvalidCheckpoint { .. })
+        ));
+
+        let snapshot = FlowContext::new("Hi".to_string(), 0.5, create_test_framework_state())
+            .checkpoint()
+            .unwrap();
+        assert!(matches!(
+            flow_process.resume_from_checkpoint(snapshot, 8),
+            Err(FlowError::InvalidCheckpoint { .. })
+        ));
+    }
+
     #[test]
     fn test_developmental_stage_progression() {
         // Test that developmental stages advance appropriately
@@ -2145,7 +3364,7 @@ mod tests {
         let boundary = BoundaryState::new("CD-SD".to_string(), 0.7, "Transitional".to_string());
 
         // When invitation is generated
-        let generator = InvitationGenerator;
+        let generator = InvitationGenerator::default();
         let invitation = generator.generate("CD", "SD", &boundary);
 
         // Then it should create tension between computational and scientific domains
@@ -2155,6 +3374,122 @@ mod tests {
         assert!(!invitation.is_empty());
     }
 
+    #[test]
+    fn test_invitation_generator_spanish_locale() {
+        let boundary = BoundaryState::new("CD-SD".to_string(), 0.7, "Transitional".to_string());
+        let generator = InvitationGenerator::with_locale(TemplateLocale::Spanish);
+
+        let invitation = generator.generate("CD", "SD", &boundary);
+        assert!(invitation.contains("computacionales"));
+        assert!(invitation.contains("evidencia científica"));
+        assert!(!invitation.contains("computational"));
+
+        for (d1, d2) in [
+            ("CD", "ED"),
+            ("SD", "CuD"),
+            ("CuD", "ED"),
+            ("CD", "CuD"),
+            ("SD", "ED"),
+        ] {
+            let invitation = generator.generate(d1, d2, &boundary);
+            assert!(
+                invitation.contains("tensión"),
+                "{}-{}: {}",
+                d1,
+                d2,
+                invitation
+            );
+        }
+
+        // Untranslated locales fall back to English
+        let german = InvitationGenerator::with_locale(TemplateLocale::German);
+        assert!(german
+            .generate("CD", "SD", &boundary)
+            .contains("computational"));
+    }
+
+    #[test]
+    fn test_interface_attention_uses_framework_locale() {
+        let mut framework_state = create_test_framework_state();
+        framework_state.locale = TemplateLocale::Spanish;
+        let mut context = FlowContext::new("Test input".to_string(), 0.7, framework_state);
+        context.boundaries = vec![BoundaryState::new(
+            "CD-SD".to_string(),
+            0.9,
+            "Transcendent".to_string(),
+        )];
+
+        InterfaceAttentionProcessor.process(&mut context).unwrap();
+        let experience = &context.interface_experiences[0];
+        assert!(experience.invitation.starts_with("Considera"));
+        assert!(experience.attention.starts_with("Centra la atención"));
+        assert!(experience.resonance.starts_with("Permite que"));
+        assert!(experience.emergence.contains("computacional-científica"));
+    }
+
+    #[test]
+    fn test_bde_generators_translate_to_spanish() {
+        let mut boundary = BoundaryState::new("CD-SD".to_string(), 0.9, "Transcendent".to_string());
+        boundary.frequency = 1.0;
+        boundary.amplitude = 0.5;
+
+        let attention = AttentionDirector::with_locale(TemplateLocale::Spanish);
+        let resonance = ResonanceFacilitator::with_locale(TemplateLocale::Spanish);
+        let emergence = EmergenceRecognizer::with_locale(TemplateLocale::Spanish);
+        for (d1, d2) in [
+            ("CD", "SD"),
+            ("CD", "ED"),
+            ("SD", "CuD"),
+            ("CuD", "ED"),
+            ("CD", "CuD"),
+            ("SD", "ED"),
+        ] {
+            let text = attention.generate(d1, d2, &boundary);
+            assert!(
+                text.contains("interfaz") || text.contains("límite"),
+                "{}",
+                text
+            );
+            assert!(resonance
+                .generate(d1, d2, &boundary)
+                .contains("ritmo natural"));
+            let text = emergence.generate_with_quality(d1, d2, &boundary, "Test input");
+            assert!(text.contains("que emerge"), "{}", text);
+            // Quality names are translated along with the template
+            assert!(
+                [
+                    "claridad",
+                    "profundidad",
+                    "apertura",
+                    "precisión",
+                    "fluidez",
+                    "resonancia",
+                    "coherencia"
+                ]
+                .iter()
+                .any(|quality| text.contains(quality)),
+                "{}",
+                text
+            );
+        }
+
+        boundary.frequency = 0.0;
+        assert!(resonance
+            .generate("CD", "SD", &boundary)
+            .contains("se mueva con naturalidad"));
+
+        // Untranslated locales fall back to English
+        assert!(AttentionDirector::with_locale(TemplateLocale::German)
+            .generate("CD", "SD", &boundary)
+            .starts_with("Focus on the interface"));
+        assert!(ResonanceFacilitator::with_locale(TemplateLocale::German)
+            .generate("CD", "SD", &boundary)
+            .starts_with("Allow understanding"));
+        assert!(EmergenceRecognizer::with_locale(TemplateLocale::German)
+            .generate_with_quality("CD", "SD", &boundary, "Test input")
+            .starts_with("Recognize"));
+    }
+
     #[test]
     fn test_invitation_generator_all_boundaries() {
         // Test that all 6 boundary combinations produce valid invitations
@@ -2168,7 +3503,7 @@ mod tests {
         ];
 
         let boundary = BoundaryState::new("test".to_string(), 0.7, "Transitional".to_string());
-        let generator = InvitationGenerator;
+        let generator = InvitationGenerator::default();
 
         for (d1, d2) in boundaries {
             let invitation = generator.generate(d1, d2, &boundary);
@@ -2193,7 +3528,7 @@ mod tests {
         let boundary = BoundaryState::new("CD-ED".to_string(), 0.8, "Transcendent".to_string());
 
         // When attention directive is generated
-        let director = AttentionDirector;
+        let director = AttentionDirector::default();
         let attention = director.generate("CD", "ED", &boundary);
 
         // Then it should focus on interface, not domains
@@ -2219,7 +3554,7 @@ mod tests {
         );
 
         // When resonance is generated
-        let facilitator = ResonanceFacilitator;
+        let facilitator = ResonanceFacilitator::default();
         let resonance_high = facilitator.generate("SD", "CuD", &high_freq_boundary);
 
         // Then it should mention rapid oscillation
@@ -2258,7 +3593,7 @@ mod tests {
         );
 
         // When resonance is generated
-        let facilitator = ResonanceFacilitator;
+        let facilitator = ResonanceFacilitator::default();
         let resonance = facilitator.generate("CuD", "ED", &boundary);
 
         // Then it should use fallback (simple natural movement, not oscillation language)
@@ -2272,7 +3607,7 @@ mod tests {
         let transcendent = BoundaryState::new("CD-SD".to_string(), 0.9, "Transcendent".to_string());
 
         // When emergence is generated
-        let recognizer = EmergenceRecognizer;
+        let recognizer = EmergenceRecognizer::default();
         let test_message = "Testing emergence generation";
         let emergence_trans =
             recognizer.generate_with_quality("CD", "SD", &transcendent, test_message);
@@ -2304,7 +3639,7 @@ mod tests {
         let high_perm = BoundaryState::new("CD-SD".to_string(), 0.9, "Transcendent".to_string());
 
         // When emergence is generated
-        let recognizer = EmergenceRecognizer;
+        let recognizer = EmergenceRecognizer::default();
         let test_message = "Precision analysis";
         let emergence = recognizer.generate_with_quality("CD", "SD", &high_perm, test_message);
 
@@ -2325,6 +3660,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_interface_attention_deprioritizes_low_rated_boundaries() {
+        let boundaries = vec![
+            BoundaryState::new("CD-SD".to_string(), 0.9, "Transcendent".to_string()),
+            BoundaryState::new("SD-CuD".to_string(), 0.9, "Transcendent".to_string()),
+        ];
+
+        let mut context =
+            FlowContext::new("Test input".to_string(), 0.7, create_test_framework_state());
+        context.boundaries = boundaries.clone();
+        InterfaceAttentionProcessor.process(&mut context).unwrap();
+        assert_eq!(context.interface_experiences.len(), 2);
+
+        // Users found CD-SD unhelpful; SD-CuD is well rated
+        let mut context =
+            FlowContext::new("Test input".to_string(), 0.7, create_test_framework_state());
+        context.boundaries = boundaries;
+        context.boundary_ratings.insert("CD-SD".to_string(), 0.0);
+        context.boundary_ratings.insert("SD-CuD".to_string(), 0.9);
+        InterfaceAttentionProcessor.process(&mut context).unwrap();
+
+        let selected: Vec<&str> = context
+            .interface_experiences
+            .iter()
+            .map(|e| e.boundary_name.as_str())
+            .collect();
+        assert_eq!(selected, vec!["SD-CuD"]);
+    }
+
     #[test]
     fn test_interface_attention_processor_uses_generators() {
         // Given a context with a transcendent boundary
@@ -2424,7 +3788,7 @@ mod tests {
         let all_boundaries = vec![boundary1.clone(), boundary2.clone(), boundary3.clone()];
 
         // When resonance is generated with context
-        let facilitator = ResonanceFacilitator;
+        let facilitator = ResonanceFacilitator::default();
         let resonance = facilitator.generate_with_context("CD", "SD", &boundary1, &all_boundaries);
 
         // Then it should mention multi-boundary synchronization
@@ -2462,7 +3826,7 @@ mod tests {
         let all_boundaries = vec![boundary1.clone(), boundary2.clone()];
 
         // When resonance is generated with context
-        let facilitator = ResonanceFacilitator;
+        let facilitator = ResonanceFacilitator::default();
         let resonance = facilitator.generate_with_context("CD", "SD", &boundary1, &all_boundaries);
 
         // Then it should fall back to single-boundary resonance (no multi-boundary mention)
@@ -2786,7 +4150,7 @@ mod tests {
 
         let boundary = BoundaryState::new("CD-SD".to_string(), 0.8, "Transcendent".to_string());
 
-        let recognizer = EmergenceRecognizer;
+        let recognizer = EmergenceRecognizer::default();
 
         // When generating emergence text with quality awareness
         let emergence = recognizer.generate_with_quality("CD", "SD", &boundary, technical_message);
@@ -2820,7 +4184,7 @@ mod tests {
 
         let boundary = BoundaryState::new("SD-CuD".to_string(), 0.7, "Transcendent".to_string());
 
-        let recognizer = EmergenceRecognizer;
+        let recognizer = EmergenceRecognizer::default();
 
         // When generating emergence for both messages
         let simple_emergence =
@@ -0,0 +1,171 @@
+// gRPC Interface
+
+mod vif;
+
+pub use vif::vif_service_client::VifServiceClient;
+pub use vif::{
+    GetSnapshotRequest, GetSnapshotResponse, ProcessInputRequest, ProcessInputResponse, Snapshot,
+};
+
+use super::VifApi;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+use vif::vif_service_server::{VifService, VifServiceServer};
+
+/// Serves a shared VifApi over gRPC
+pub struct GrpcServer {
+    api: Arc<Mutex<VifApi>>,
+    addr: SocketAddr,
+}
+
+impl GrpcServer {
+    pub fn new(api: Arc<Mutex<VifApi>>, addr: SocketAddr) -> Self {
+        Self { api, addr }
+    }
+
+    /// Listen on the configured address until the transport fails
+    pub async fn start(self) -> Result<(), tonic::transport::Error> {
+        tonic::transport::Server::builder()
+            .add_service(VifServiceServer::new(VifGrpcService { api: self.api }))
+            .serve(self.addr)
+            .await
+    }
+}
+
+struct VifGrpcService {
+    api: Arc<Mutex<VifApi>>,
+}
+
+fn invalid_user_id(e: uuid::Error) -> Status {
+    Status::invalid_argument(format!("invalid user_id: {}", e))
+}
+
+#[tonic::async_trait]
+impl VifService for VifGrpcService {
+    async fn process_input(
+        &self,
+        request: Request<ProcessInputRequest>,
+    ) -> Result<Response<ProcessInputResponse>, Status> {
+        let request = request.into_inner();
+        let user_id = Uuid::parse_str(&request.user_id).map_err(invalid_user_id)?;
+        let response = self
+            .api
+            .lock()
+            .await
+            .process_input(&request.input, user_id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(ProcessInputResponse { response }))
+    }
+
+    async fn get_snapshot(
+        &self,
+        request: Request<GetSnapshotRequest>,
+    ) -> Result<Response<GetSnapshotResponse>, Status> {
+        let user_id = Uuid::parse_str(&request.into_inner().user_id).map_err(invalid_user_id)?;
+        let snapshot = self
+            .api
+            .lock()
+            .await
+            .get_latest_snapshot(user_id)
+            .await
+            .map(|snapshot| Snapshot {
+                id: snapshot.id().to_string(),
+                timestamp: snapshot.timestamp(),
+                qualities: snapshot.qualities().to_vec(),
+                identity_anchor_ids: snapshot.identity_anchor_ids().clone(),
+                pattern_ids: snapshot.pattern_ids().clone(),
+            });
+        Ok(Response::new(GetSnapshotResponse { snapshot }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryManager;
+    use crate::mock_llm::MockLlm;
+    use crate::prompt_engine::FrameworkState;
+    use crate::test_utils::{insert_test_user, setup_test_db};
+    use std::time::Duration;
+    use tonic::transport::Channel;
+
+    async fn connect(addr: SocketAddr) -> VifServiceClient<Channel> {
+        // The server starts in the background, so retry until it accepts
+        for _ in 0..50 {
+            if let Ok(client) = VifServiceClient::connect(format!("http://{}", addr)).await {
+                return client;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("gRPC server did not start on {}", addr);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_server_serves_process_input_and_snapshots() {
+        let db_pool = setup_test_db().await.unwrap();
+        let user_id = Uuid::new_v4();
+        insert_test_user(&db_pool, user_id).await;
+        let api = VifApi::from_parts(
+            Box::new(MockLlm::echo()),
+            FrameworkState::default(),
+            MemoryManager {
+                db_pool,
+                organization_id: None,
+            },
+        );
+
+        // Reserve a free port, then hand it to the server
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = GrpcServer::new(Arc::new(Mutex::new(api)), addr);
+        let handle = tokio::spawn(server.start());
+        let mut client = connect(addr).await;
+
+        let empty = client
+            .get_snapshot(GetSnapshotRequest {
+                user_id: user_id.to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(empty.snapshot.is_none());
+
+        let response = client
+            .process_input(ProcessInputRequest {
+                user_id: user_id.to_string(),
+                input: "How do boundaries shape understanding?".to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!response.response.is_empty());
+
+        let snapshot = client
+            .get_snapshot(GetSnapshotRequest {
+                user_id: user_id.to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .snapshot
+            .expect("processing input should save a snapshot");
+        assert_eq!(snapshot.qualities.len(), 7);
+
+        let status = client
+            .process_input(ProcessInputRequest {
+                user_id: "not-a-uuid".to_string(),
+                input: "Hello".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        handle.abort();
+    }
+}
@@ -65,18 +65,18 @@ mod tests {
         let mut registry = DomainRegistry::new();
         registry.register_domain(Box::new(ComputationalDomain));
         registry.register_domain(Box::new(ScientificDomain));
-        registry.register_domain(Box::new(CulturalDomain));
+        registry.register_domain(Box::new(CulturalDomain::default()));
         registry.register_domain(Box::new(ExperientialDomain));
 
-        FrameworkState {
-            domain_registry: registry,
-            boundaries: vec![
+        FrameworkState::new(
+            registry,
+            vec![
                 BoundaryState::new("CD-SD".to_string(), 0.5, "Maintained".to_string()),
                 BoundaryState::new("SD-CuD".to_string(), 0.6, "Maintained".to_string()),
                 BoundaryState::new("CuD-ED".to_string(), 0.7, "Active".to_string()),
             ],
-            identity: "Test Identity".to_string(),
-        }
+            "Test Identity".to_string(),
+        )
     }
 
     #[test]
@@ -11,6 +11,7 @@ pub enum LlmError {
     NetworkError {
         message: String,
         status_code: Option<u16>,
+        provider_name: Option<String>,
     },
 
     /// JSON parsing or deserialization errors
@@ -46,7 +47,101 @@ pub enum LlmError {
     },
 
     /// Authentication/authorization errors
-    AuthError { message: String },
+    AuthError {
+        message: String,
+        provider_name: Option<String>,
+    },
+
+    /// User input matched a prompt injection heuristic and was rejected
+    InjectionDetected { pattern: String },
+
+    /// Request exceeded the configured client timeout
+    Timeout { provider: String, duration_ms: u64 },
+
+    /// The user used up their per-user request or token allowance
+    UserRateLimited { retry_after_secs: u64 },
+}
+
+/// Data-free discriminant of LlmError, used to match on error categories
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LlmErrorKind {
+    NetworkError,
+    JsonParseError,
+    ApiError,
+    InvalidResponseFormat,
+    ConfigError,
+    UnsupportedProvider,
+    RateLimitError,
+    AuthError,
+    InjectionDetected,
+    Timeout,
+    UserRateLimited,
+}
+
+impl LlmError {
+    pub fn kind(&self) -> LlmErrorKind {
+        match self {
+            LlmError::NetworkError { .. } => LlmErrorKind::NetworkError,
+            LlmError::JsonParseError { .. } => LlmErrorKind::JsonParseError,
+            LlmError::ApiError { .. } => LlmErrorKind::ApiError,
+            LlmError::InvalidResponseFormat { .. } => LlmErrorKind::InvalidResponseFormat,
+            LlmError::ConfigError { .. } => LlmErrorKind::ConfigError,
+            LlmError::UnsupportedProvider { .. } => LlmErrorKind::UnsupportedProvider,
+            LlmError::RateLimitError { .. } => LlmErrorKind::RateLimitError,
+            LlmError::AuthError { .. } => LlmErrorKind::AuthError,
+            LlmError::InjectionDetected { .. } => LlmErrorKind::InjectionDetected,
+            LlmError::Timeout { .. } => LlmErrorKind::Timeout,
+            LlmError::UserRateLimited { .. } => LlmErrorKind::UserRateLimited,
+        }
+    }
+
+    /// Classify a provider's non-success HTTP response. The message comes
+    /// from an `{"error": {"message": ...}}` body when present.
+    pub fn from_status(status_code: u16, body: &str, retry_after: Option<u64>) -> Self {
+        let detail = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|json| json["error"]["message"].as_str().map(str::to_string))
+            .unwrap_or_else(|| body.trim().to_string());
+
+        match status_code {
+            401 | 403 => LlmError::AuthError {
+                message: format!("Authentication failed ({}): {}", status_code, detail),
+                provider_name: None,
+            },
+            429 => LlmError::RateLimitError {
+                message: format!("Rate limit exceeded: {}", detail),
+                retry_after,
+            },
+            400..=499 => LlmError::ApiError {
+                message: format!("Client error ({}): {}", status_code, detail),
+                error_type: Some("client_error".to_string()),
+                status_code: Some(status_code),
+            },
+            _ => LlmError::ApiError {
+                message: format!("Server error ({}): {}", status_code, detail),
+                error_type: Some("server_error".to_string()),
+                status_code: Some(status_code),
+            },
+        }
+    }
+
+    /// Attach the provider name to network and authentication errors that lack one
+    pub fn with_provider(mut self, provider: &str) -> Self {
+        if let LlmError::NetworkError { provider_name, .. }
+        | LlmError::AuthError { provider_name, .. } = &mut self
+        {
+            provider_name.get_or_insert_with(|| provider.to_string());
+        }
+        self
+    }
+}
+
+/// "[provider: name] " when the provider is known
+fn provider_prefix(provider_name: &Option<String>) -> String {
+    provider_name
+        .as_ref()
+        .map(|name| format!("[provider: {}] ", name))
+        .unwrap_or_default()
 }
 
 impl fmt::Display for LlmError {
@@ -55,8 +150,15 @@ impl fmt::Display for LlmError {
             LlmError::NetworkError {
                 message,
                 status_code,
+                provider_name,
             } => {
-                write!(f, "Network error: {} (status: {:?})", message, status_code)
+                write!(
+                    f,
+                    "{}Network error: {} (status: {:?})",
+                    provider_prefix(provider_name),
+                    message,
+                    status_code
+                )
             }
             LlmError::JsonParseError {
                 message,
@@ -102,8 +204,36 @@ impl fmt::Display for LlmError {
                     message, retry_after
                 )
             }
-            LlmError::AuthError { message } => {
-                write!(f, "Authentication error: {}", message)
+            LlmError::AuthError {
+                message,
+                provider_name,
+            } => {
+                write!(
+                    f,
+                    "{}Authentication error: {}",
+                    provider_prefix(provider_name),
+                    message
+                )
+            }
+            LlmError::InjectionDetected { pattern } => {
+                write!(f, "Prompt injection detected: matched '{}'", pattern)
+            }
+            LlmError::Timeout {
+                provider,
+                duration_ms,
+            } => {
+                write!(
+                    f,
+                    "[provider: {}] Request timed out after {}ms",
+                    provider, duration_ms
+                )
+            }
+            LlmError::UserRateLimited { retry_after_secs } => {
+                write!(
+                    f,
+                    "User rate limit exceeded (retry after: {}s)",
+                    retry_after_secs
+                )
             }
         }
     }
@@ -121,12 +251,14 @@ impl From<reqwest::Error> for LlmError {
             LlmError::NetworkError {
                 message: format!("Network error: {}", err),
                 status_code,
+                provider_name: None,
             }
         } else if err.is_status() {
             // Check for specific status codes
             match status_code {
                 Some(401) | Some(403) => LlmError::AuthError {
                     message: format!("Authentication failed: {}", err),
+                    provider_name: None,
                 },
                 Some(429) => LlmError::RateLimitError {
                     message: format!("Rate limit exceeded: {}", err),
@@ -145,6 +277,7 @@ impl From<reqwest::Error> for LlmError {
                 _ => LlmError::NetworkError {
                     message: format!("HTTP error: {}", err),
                     status_code,
+                    provider_name: None,
                 },
             }
         } else if err.is_decode() {
@@ -156,6 +289,7 @@ impl From<reqwest::Error> for LlmError {
             LlmError::NetworkError {
                 message: format!("Request error: {}", err),
                 status_code,
+                provider_name: None,
             }
         }
     }
@@ -216,6 +350,7 @@ mod tests {
     fn test_auth_error() {
         let err = LlmError::AuthError {
             message: "Invalid API key".to_string(),
+            provider_name: None,
         };
         let display = format!("{}", err);
         assert!(display.contains("Authentication error"));
@@ -228,6 +363,7 @@ mod tests {
         let err = LlmError::NetworkError {
             message: "Connection timeout after 30s".to_string(),
             status_code: None,
+            provider_name: None,
         };
         let display = format!("{}", err);
         assert!(display.contains("Network error"));
@@ -235,6 +371,46 @@ mod tests {
         assert!(display.contains("status: None"));
     }
 
+    #[test]
+    fn test_errors_include_provider_name() {
+        let errors = [
+            LlmError::NetworkError {
+                message: "Connection reset".to_string(),
+                status_code: None,
+                provider_name: Some("openai".to_string()),
+            },
+            LlmError::AuthError {
+                message: "Invalid API key".to_string(),
+                provider_name: None,
+            }
+            .with_provider("openai"),
+            LlmError::Timeout {
+                provider: "openai".to_string(),
+                duration_ms: 100,
+            },
+        ];
+        for err in errors {
+            assert!(
+                err.to_string().starts_with("[provider: openai] "),
+                "{}",
+                err
+            );
+        }
+
+        // An existing provider name is kept; other variants are unchanged
+        let err = LlmError::AuthError {
+            message: "Invalid API key".to_string(),
+            provider_name: Some("anthropic".to_string()),
+        }
+        .with_provider("openai");
+        assert!(err.to_string().contains("[provider: anthropic]"));
+        let err = LlmError::ConfigError {
+            message: "Missing key".to_string(),
+        }
+        .with_provider("openai");
+        assert!(!err.to_string().contains("provider:"));
+    }
+
     #[test]
     fn test_rate_limit_error_with_retry() {
         // Test rate limiting with retry_after information
@@ -1,16 +1,22 @@
-use crate::prompt_engine::{BoundaryState, DomainState};
-
+use crate::autonomous_judgement::PrototypeFitness;
+use crate::flow_process::{
+    IdentityAnchor as FlowIdentityAnchor, InterfaceExperienceRating, PhenomenologicalQuality,
+};
+use crate::prompt_engine::{
+    BoundaryState, DomainState, FrameworkState, IdentityHistory, IdentityVersion,
+};
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{types::Uuid, Row, SqlitePool};
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct StateSnapshot {
-    pub domains: Vec<DomainState>,
-    pub boundaries: Vec<BoundaryState>,
-    pub patterns: Vec<String>,
-}
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow},
+    types::Uuid,
+    Row, Sqlite, SqlitePool, Transaction,
+};
 
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompactInterfaceState {
@@ -36,6 +42,9 @@ pub struct CompactStateSnapshot {
     boundary_states: u64,
     interface_states: Vec<CompactInterfaceState>,
     qualities: [u8; 7],
+    /// Permeability-weighted quality across all boundaries, in the order
+    /// clarity, depth, openness, precision, fluidity, resonance, coherence
+    aggregate_quality: Option<[u8; 7]>,
     identity_anchor_ids: Vec<String>,
     pattern_ids: Vec<String>,
     developmental_stage: u8,
@@ -66,6 +75,10 @@ impl CompactStateSnapshot {
         &self.qualities
     }
 
+    pub fn aggregate_quality(&self) -> Option<&[u8; 7]> {
+        self.aggregate_quality.as_ref()
+    }
+
     pub fn identity_anchor_ids(&self) -> &Vec<String> {
         &self.identity_anchor_ids
     }
@@ -73,6 +86,121 @@ impl CompactStateSnapshot {
     pub fn pattern_ids(&self) -> &Vec<String> {
         &self.pattern_ids
     }
+
+    /// Compare this snapshot against a later one.
+    /// Deltas are expressed as `other - self`, normalized to the 0.0-1.0 range
+    pub fn diff(&self, other: &CompactStateSnapshot) -> SnapshotDiff {
+        let mut domain_changes = HashMap::new();
+        for key in self.domain_values.keys().chain(other.domain_values.keys()) {
+            let before = Self::domain_activation(&self.domain_values, *key);
+            let after = Self::domain_activation(&other.domain_values, *key);
+            domain_changes.insert(domain_name_for_key(*key), after - before);
+        }
+
+        let mut boundary_permeability_changes = HashMap::new();
+        let before = Self::interface_permeabilities(&self.interface_states);
+        let after = Self::interface_permeabilities(&other.interface_states);
+        for name in before.keys().chain(after.keys()) {
+            let delta =
+                after.get(name).copied().unwrap_or(0.0) - before.get(name).copied().unwrap_or(0.0);
+            boundary_permeability_changes.insert(name.clone(), delta);
+        }
+
+        let mut quality_deltas = [0.0; 7];
+        for (i, delta) in quality_deltas.iter_mut().enumerate() {
+            *delta = (other.qualities[i] as f64 - self.qualities[i] as f64) / 255.0;
+        }
+
+        SnapshotDiff {
+            domain_changes,
+            boundary_permeability_changes,
+            quality_deltas,
+            developmental_stage_changed: self.developmental_stage != other.developmental_stage,
+        }
+    }
+
+    /// Activation of each stored domain, keyed by domain name
+    pub fn domain_activations(&self) -> HashMap<String, f64> {
+        self.domain_values
+            .keys()
+            .map(|key| {
+                (
+                    domain_name_for_key(*key),
+                    Self::domain_activation(&self.domain_values, *key),
+                )
+            })
+            .collect()
+    }
+
+    /// Cosine similarity between two snapshots' domain activation vectors.
+    /// 0.0 when either snapshot has no active domains.
+    pub fn domain_alignment(&self, other: &CompactStateSnapshot) -> f64 {
+        let a = self.domain_activations();
+        let b = other.domain_activations();
+
+        let dot: f64 = a
+            .iter()
+            .map(|(name, value)| value * b.get(name).copied().unwrap_or(0.0))
+            .sum();
+        let norm_a = a.values().map(|v| v * v).sum::<f64>().sqrt();
+        let norm_b = b.values().map(|v| v * v).sum::<f64>().sqrt();
+        if norm_a == 0.0 || norm_b == 0.0 {
+            return 0.0;
+        }
+        dot / (norm_a * norm_b)
+    }
+
+    fn domain_activation(domain_values: &HashMap<u8, Vec<u8>>, key: u8) -> f64 {
+        // Domain values are stored as percentages; the first value is the activation
+        domain_values
+            .get(&key)
+            .and_then(|values| values.first())
+            .map(|v| *v as f64 / 100.0)
+            .unwrap_or(0.0)
+    }
+
+    fn interface_permeabilities(
+        interface_states: &[CompactInterfaceState],
+    ) -> HashMap<String, f64> {
+        interface_states
+            .iter()
+            .map(|i| {
+                (
+                    format!("{}-{}", i.domains.0, i.domains.1),
+                    i.permeability as f64 / 255.0,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Differences between two snapshots, used to monitor drift in framework state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub domain_changes: HashMap<String, f64>,
+    pub boundary_permeability_changes: HashMap<String, f64>,
+    pub quality_deltas: [f64; 7],
+    pub developmental_stage_changed: bool,
+}
+
+impl SnapshotDiff {
+    /// Largest absolute change in any domain activation
+    pub fn max_domain_change(&self) -> f64 {
+        self.domain_changes
+            .values()
+            .fold(0.0, |max, delta| max.max(delta.abs()))
+    }
+}
+
+/// Inverse of the domain key mapping used when compressing snapshots
+fn domain_name_for_key(key: u8) -> String {
+    match key {
+        0 => "CD".to_string(),
+        1 => "SD".to_string(),
+        2 => "CuD".to_string(),
+        3 => "ED".to_string(),
+        _ => format!("unknown_{}", key),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -90,6 +218,8 @@ struct SnapshotMetadata {
     interface_states: Vec<CompactInterfaceState>,
     qualities: [u8; 7],
     developmental_stage: u8,
+    #[serde(default)]
+    aggregate_quality: Option<[u8; 7]>,
 }
 
 impl CompactInterfaceState {
@@ -102,18 +232,225 @@ impl CompactInterfaceState {
     }
 }
 
+/// A pattern observed for a user, counted across requests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternRecord {
+    pub pattern_id: Uuid,
+    pub user_id: Uuid,
+    pub description: String,
+    pub occurrence_count: u32,
+    pub first_seen: chrono::DateTime<chrono::Utc>,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+}
+
+/// What MemoryManager::anonymize_user removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnonymizationReport {
+    /// Flow executions deleted along with their input and output text
+    pub turns_deleted: u32,
+    pub snapshots_deleted: u32,
+}
+
+/// What MemoryManager::transfer_user_data copied to the target database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferReport {
+    pub snapshots_transferred: u32,
+    /// Flow executions, which hold each turn's input and output text
+    pub turns_transferred: u32,
+    /// Every row copied, across all tables
+    pub rows_transferred: u32,
+}
+
+/// A domain's activation as recorded in one snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DomainActivationPoint {
+    pub timestamp: DateTime<Utc>,
+    pub activation: f64,
+    pub snapshot_id: Uuid,
+}
+
+/// Per-user tables in foreign key order, with the filter selecting a user's rows
+const USER_DATA_TABLES: &[(&str, &str)] = &[
+    ("users", "id = ?"),
+    ("user_profiles", "user_id = ?"),
+    ("framework_states", "user_id = ?"),
+    ("identity_anchors", "user_id = ?"),
+    ("identity_versions", "user_id = ?"),
+    ("user_patterns", "user_id = ?"),
+    ("interface_experience_ratings", "user_id = ?"),
+    ("state_snapshots", "user_id = ?"),
+    ("flow_process_executions", "user_id = ?"),
+    (
+        "interface_experiences",
+        "flow_execution_id IN (SELECT id FROM flow_process_executions WHERE user_id = ?)",
+    ),
+    (
+        "phenomenological_qualities",
+        "flow_execution_id IN (SELECT id FROM flow_process_executions WHERE user_id = ?)",
+    ),
+];
+
+/// Boundaries need this many ratings before their average affects selection
+const MIN_RATINGS_FOR_AVERAGE: i64 = 3;
+
+/// Connection pool sizing and timeouts for the memory database
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseConfig {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a query may wait for a pooled connection or a locked database
+    pub query_timeout: Duration,
+    pub idle_timeout: Duration,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        // Matches the SQLx pool defaults
+        Self {
+            max_connections: 10,
+            min_connections: 0,
+            query_timeout: Duration::from_secs(30),
+            idle_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Read pool settings from the environment, falling back to defaults for
+    /// unset or unparsable values:
+    /// DATABASE_MAX_CONNECTIONS, DATABASE_MIN_CONNECTIONS,
+    /// DATABASE_QUERY_TIMEOUT_MS, DATABASE_IDLE_TIMEOUT_SECS
+    pub fn from_env() -> Self {
+        fn read<T: FromStr>(key: &str) -> Option<T> {
+            std::env::var(key).ok().and_then(|value| value.parse().ok())
+        }
+
+        let defaults = Self::default();
+        Self {
+            max_connections: read("DATABASE_MAX_CONNECTIONS").unwrap_or(defaults.max_connections),
+            min_connections: read("DATABASE_MIN_CONNECTIONS").unwrap_or(defaults.min_connections),
+            query_timeout: read("DATABASE_QUERY_TIMEOUT_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.query_timeout),
+            idle_timeout: read("DATABASE_IDLE_TIMEOUT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.idle_timeout),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct MemoryManager {
     pub(crate) db_pool: SqlitePool,
+    /// When set, only users in this organization can be read or written
+    pub(crate) organization_id: Option<Uuid>,
 }
 
 impl MemoryManager {
-    pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
-        let db_pool = SqlitePool::connect(database_url).await?;
+    pub async fn from_config(
+        database_url: &str,
+        config: DatabaseConfig,
+    ) -> Result<Self, sqlx::Error> {
+        let connect_options =
+            SqliteConnectOptions::from_str(database_url)?.busy_timeout(config.query_timeout);
+        let db_pool = SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(config.query_timeout)
+            .idle_timeout(config.idle_timeout)
+            .connect_with(connect_options)
+            .await?;
         // Note: Migrations should be run separately via `sqlx migrate run`
         // We don't run schema.sql here because it contains PostgreSQL-specific syntax
-        Ok(Self { db_pool })
+        Ok(Self {
+            db_pool,
+            organization_id: None,
+        })
+    }
+
+    /// A private in-memory database holding a copy of one user's data, for
+    /// runs that must not write to this manager's database. A placeholder
+    /// user row is created if the user has no data to copy.
+    pub async fn sandbox_for_user(&self, user_id: Uuid) -> Result<Self, sqlx::Error> {
+        // A single connection that never idles out keeps the in-memory database alive
+        let db_pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+        sqlx::migrate!("./migrations").run(&db_pool).await?;
+
+        let report = self.transfer_user_data(user_id, &db_pool).await?;
+        if report.rows_transferred == 0 {
+            sqlx::query(
+                "INSERT INTO users (id, provider, provider_id, email) VALUES (?, 'sandbox', ?, '')",
+            )
+            .bind(user_id.as_bytes().to_vec())
+            .bind(user_id.to_string())
+            .execute(&db_pool)
+            .await?;
+        }
+
+        Ok(Self {
+            db_pool,
+            organization_id: None,
+        })
+    }
+
+    /// A manager sharing this pool that only serves users belonging to
+    /// `organization_id`. Calls for any other user fail with RowNotFound.
+    pub fn with_organization(&self, organization_id: Uuid) -> Self {
+        Self {
+            db_pool: self.db_pool.clone(),
+            organization_id: Some(organization_id),
+        }
+    }
+
+    /// Assign a user to an organization
+    pub async fn set_user_organization(
+        &self,
+        user_id: Uuid,
+        organization_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        sqlx::query("UPDATE users SET organization_id = ? WHERE id = ?")
+            .bind(organization_id.as_bytes().to_vec())
+            .bind(user_id.as_bytes().to_vec())
+            .execute(&self.db_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Reject users outside this manager's organization, if it is scoped
+    async fn ensure_in_organization(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        self.ensure_in_organization_with(&self.db_pool, user_id)
+            .await
+    }
+
+    async fn ensure_in_organization_with<'e, E>(
+        &self,
+        executor: E,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error>
+    where
+        E: sqlx::SqliteExecutor<'e>,
+    {
+        let Some(organization_id) = self.organization_id else {
+            return Ok(());
+        };
+        sqlx::query("SELECT 1 FROM users WHERE id = ? AND organization_id = ?")
+            .bind(user_id.as_bytes().to_vec())
+            .bind(organization_id.as_bytes().to_vec())
+            .fetch_optional(executor)
+            .await?
+            .map(|_| ())
+            .ok_or(sqlx::Error::RowNotFound)
     }
 
+    /// Compress and persist the current state; `aggregate_quality` is the
+    /// session-level quality reading from the flow, if any
+    #[cfg(test)]
     pub async fn create_snapshot(
         &self,
         domains: Vec<DomainState>,
@@ -121,11 +458,26 @@ impl MemoryManager {
         patterns: Vec<String>,
         user_id: Uuid,
         user_input: &str,
+        aggregate_quality: Option<&PhenomenologicalQuality>,
     ) -> Result<(), sqlx::Error> {
-        let compact_snapshot =
-            self.compress_snapshot(domains, boundaries, patterns, user_id, user_input);
-        self.save_snapshot_to_db(&compact_snapshot).await?;
-        Ok(())
+        self.ensure_in_organization(user_id).await?;
+        let compact_snapshot = self.compress_snapshot(
+            domains,
+            boundaries,
+            patterns,
+            user_id,
+            user_input,
+            aggregate_quality,
+        );
+        Self::insert_snapshot(&self.db_pool, &compact_snapshot).await
+    }
+
+    /// Start a transaction for saving several records atomically
+    pub async fn begin_transaction(&self) -> Result<MemoryTransaction, sqlx::Error> {
+        Ok(MemoryTransaction {
+            manager: self.clone(),
+            tx: self.db_pool.begin().await?,
+        })
     }
 
     fn compress_snapshot(
@@ -135,6 +487,7 @@ impl MemoryManager {
         patterns: Vec<String>,
         user_id: Uuid,
         user_input: &str,
+        aggregate_quality: Option<&PhenomenologicalQuality>,
     ) -> CompactStateSnapshot {
         let mut domain_values = HashMap::new();
         for d in domains.clone() {
@@ -178,6 +531,7 @@ impl MemoryManager {
             boundary_states,
             interface_states: self.compress_interface_states(&boundaries),
             qualities: self.compress_qualities(&boundaries),
+            aggregate_quality: aggregate_quality.map(Self::compress_quality),
             identity_anchor_ids: self.create_identity_anchors(&domains, &boundaries, user_input),
             pattern_ids: patterns.to_vec(),
             developmental_stage: self.calculate_developmental_stage(&domains, &boundaries),
@@ -275,6 +629,19 @@ impl MemoryManager {
             .collect()
     }
 
+    fn compress_quality(quality: &PhenomenologicalQuality) -> [u8; 7] {
+        [
+            quality.clarity,
+            quality.depth,
+            quality.openness,
+            quality.precision,
+            quality.fluidity,
+            quality.resonance,
+            quality.coherence,
+        ]
+        .map(|value| (value.clamp(0.0, 1.0) * 255.0) as u8)
+    }
+
     fn compress_qualities(&self, boundaries: &[BoundaryState]) -> [u8; 7] {
         let mut qualities = [0; 7];
         let quality_names = [
@@ -297,10 +664,13 @@ impl MemoryManager {
         qualities
     }
 
-    async fn save_snapshot_to_db(
-        &self,
+    async fn insert_snapshot<'e, E>(
+        executor: E,
         compact_snapshot: &CompactStateSnapshot,
-    ) -> Result<(), sqlx::Error> {
+    ) -> Result<(), sqlx::Error>
+    where
+        E: sqlx::SqliteExecutor<'e>,
+    {
         let id =
             uuid::Uuid::parse_str(&compact_snapshot.id).map_err(|e| sqlx::Error::ColumnDecode {
                 index: "id".to_string(),
@@ -336,6 +706,7 @@ impl MemoryManager {
             interface_states: compact_snapshot.interface_states.clone(),
             qualities: compact_snapshot.qualities,
             developmental_stage: compact_snapshot.developmental_stage,
+            aggregate_quality: compact_snapshot.aggregate_quality,
         };
         let metadata_json =
             serde_json::to_string(&metadata).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
@@ -352,7 +723,7 @@ impl MemoryManager {
             .bind(pattern_ids_json)
             .bind(identity_anchors_json)
             .bind(metadata_json)
-            .execute(&self.db_pool)
+            .execute(executor)
             .await?;
         Ok(())
     }
@@ -361,199 +732,842 @@ impl MemoryManager {
         &self,
         user_id: Uuid,
     ) -> Result<Option<CompactStateSnapshot>, sqlx::Error> {
-        let row = sqlx::query(
+        Ok(self.get_recent_snapshots(user_id, 1).await?.pop())
+    }
+
+    /// Fetch the most recent snapshots for a user, newest first
+    pub async fn get_recent_snapshots(
+        &self,
+        user_id: Uuid,
+        limit: usize,
+    ) -> Result<Vec<CompactStateSnapshot>, sqlx::Error> {
+        self.get_snapshots(user_id, limit, None).await
+    }
+
+    /// Page backwards through a user's snapshots, newest first.
+    /// Pass the id of the last snapshot returned as `before` to fetch the
+    /// next page. Snapshots are ordered by (timestamp, id), so ones sharing a
+    /// timestamp are neither skipped nor repeated across pages, which a
+    /// timestamp cursor could not guarantee. A `before` id that is not one of
+    /// the user's snapshots yields an empty page.
+    pub async fn get_snapshots(
+        &self,
+        user_id: Uuid,
+        limit: usize,
+        before: Option<Uuid>,
+    ) -> Result<Vec<CompactStateSnapshot>, sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        let before = before.map(|id| id.as_bytes().to_vec());
+        let rows = sqlx::query(
             "SELECT id, user_id, timestamp, domain_states, boundary_states, pattern_ids, identity_anchors, metadata
              FROM state_snapshots
              WHERE user_id = ?
-             ORDER BY timestamp DESC
-             LIMIT 1"
+               AND (? IS NULL OR (timestamp, id) < (
+                   SELECT timestamp, id FROM state_snapshots WHERE id = ? AND user_id = ?
+               ))
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?"
         )
             .bind(user_id.as_bytes().to_vec())
+            .bind(before.clone())
+            .bind(before)
+            .bind(user_id.as_bytes().to_vec())
+            .bind(limit as i64)
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        rows.iter().map(Self::snapshot_from_row).collect()
+    }
+
+    /// Look up a single snapshot; snapshots outside a scoped organization are not found
+    pub async fn get_snapshot_by_id(
+        &self,
+        snapshot_id: Uuid,
+    ) -> Result<Option<CompactStateSnapshot>, sqlx::Error> {
+        let organization_id = self.organization_id.map(|id| id.as_bytes().to_vec());
+        let row = sqlx::query(
+            "SELECT id, user_id, timestamp, domain_states, boundary_states, pattern_ids, identity_anchors, metadata
+             FROM state_snapshots
+             WHERE id = ?
+               AND (? IS NULL OR user_id IN (SELECT id FROM users WHERE organization_id = ?))"
+        )
+            .bind(snapshot_id.as_bytes().to_vec())
+            .bind(organization_id.clone())
+            .bind(organization_id)
             .fetch_optional(&self.db_pool)
             .await?;
 
-        if let Some(row) = row {
-            // Deserialize from separate columns
-            let id: Vec<u8> = row.get("id");
-            let user_id_bytes: Vec<u8> = row.get("user_id");
-            let timestamp_str: String = row.get("timestamp");
-            let domain_states_json: String = row.get("domain_states");
-            let boundary_states_json: String = row.get("boundary_states");
-            let pattern_ids_json: String = row.get("pattern_ids");
-            let identity_anchors_json: String = row.get("identity_anchors");
-            let metadata_json: Option<String> = row.get("metadata");
-
-            let id_uuid = Uuid::from_slice(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
-            let user_id_uuid =
-                Uuid::from_slice(&user_id_bytes).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
-            let timestamp = chrono::DateTime::parse_from_rfc3339(&timestamp_str)
-                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
-                .timestamp();
-
-            let domain_values: HashMap<u8, Vec<u8>> = serde_json::from_str(&domain_states_json)
-                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
-            let boundary_states: u64 = serde_json::from_str(&boundary_states_json)
-                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
-            let pattern_ids: Vec<String> = serde_json::from_str(&pattern_ids_json)
-                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
-            let identity_anchor_ids: Vec<String> = serde_json::from_str(&identity_anchors_json)
-                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
-
-            // Deserialize metadata (interface_states, qualities, developmental_stage)
-            // Default to empty/zero if metadata column is null (backward compatibility)
-            let metadata = if let Some(json) = metadata_json {
-                serde_json::from_str::<SnapshotMetadata>(&json)
-                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
-            } else {
-                SnapshotMetadata {
-                    interface_states: vec![],
-                    qualities: [0; 7],
-                    developmental_stage: 0,
-                }
-            };
+        row.as_ref().map(Self::snapshot_from_row).transpose()
+    }
 
-            Ok(Some(CompactStateSnapshot {
-                id: id_uuid.to_string(),
-                timestamp,
-                user_id: user_id_uuid.to_string(),
-                domain_values,
-                boundary_states,
-                interface_states: metadata.interface_states,
-                qualities: metadata.qualities,
-                identity_anchor_ids,
-                pattern_ids,
-                developmental_stage: metadata.developmental_stage,
-            }))
-        } else {
-            Ok(None)
-        }
+    /// Activation of `domain_name` across the user's `limit` most recent
+    /// snapshots, oldest first. Snapshots without the domain are skipped.
+    pub async fn get_domain_history(
+        &self,
+        user_id: Uuid,
+        domain_name: &str,
+        limit: usize,
+    ) -> Result<Vec<DomainActivationPoint>, sqlx::Error> {
+        let snapshots = self.get_recent_snapshots(user_id, limit).await?;
+
+        // Snapshots come back newest first
+        snapshots
+            .iter()
+            .rev()
+            .filter_map(|snapshot| {
+                let activation = *snapshot.domain_activations().get(domain_name)?;
+                Some(Self::activation_point(snapshot, activation))
+            })
+            .collect()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::prompt_engine::{BoundaryState, DomainState};
-    use crate::test_utils::setup_test_db;
+    fn activation_point(
+        snapshot: &CompactStateSnapshot,
+        activation: f64,
+    ) -> Result<DomainActivationPoint, sqlx::Error> {
+        Ok(DomainActivationPoint {
+            timestamp: DateTime::from_timestamp(snapshot.timestamp, 0).ok_or_else(|| {
+                sqlx::Error::Decode(format!("invalid timestamp {}", snapshot.timestamp).into())
+            })?,
+            activation,
+            snapshot_id: Uuid::parse_str(&snapshot.id)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        })
+    }
 
-    #[tokio::test]
-    async fn test_memory_manager() {
-        // Use in-memory database for testing
-        let db_pool = setup_test_db().await.unwrap();
-        let memory_manager = MemoryManager { db_pool };
+    /// Index an identity anchor so it can be searched independently of snapshots.
+    /// Saving an anchor again updates its confidence and domains.
+    #[cfg(test)]
+    pub async fn save_identity_anchor(
+        &self,
+        user_id: Uuid,
+        anchor: &FlowIdentityAnchor,
+    ) -> Result<(), sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        Self::insert_identity_anchor(&self.db_pool, user_id, anchor).await
+    }
+
+    async fn insert_identity_anchor<'e, E>(
+        executor: E,
+        user_id: Uuid,
+        anchor: &FlowIdentityAnchor,
+    ) -> Result<(), sqlx::Error>
+    where
+        E: sqlx::SqliteExecutor<'e>,
+    {
+        let domains_json = serde_json::to_string(&anchor.domains)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
 
-        // Create a test user first (required by foreign key constraint)
-        let user_id = Uuid::new_v4();
         sqlx::query(
-            "INSERT INTO users (id, provider, provider_id, email, name, created_at, last_login)
-             VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))",
+            "INSERT INTO identity_anchors (anchor_id, user_id, anchor_type, description, confidence, domains)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(user_id, anchor_type, description) DO UPDATE SET
+                 confidence = excluded.confidence,
+                 domains = excluded.domains",
         )
+        .bind(Uuid::new_v4().as_bytes().to_vec())
         .bind(user_id.as_bytes().to_vec())
-        .bind("test")
-        .bind(user_id.to_string())
-        .bind("test@example.com")
-        .bind("Test User")
-        .execute(&memory_manager.db_pool)
-        .await
-        .unwrap();
-
-        let domains = vec![
-            DomainState {
-                name: "CD".to_string(),
-                state: "Computational Domain State".to_string(),
-            },
-            DomainState {
-                name: "SD".to_string(),
-                state: "Scientific Domain State".to_string(),
-            },
-        ];
-
-        let boundaries = vec![
-            BoundaryState::new("CD-SD".to_string(), 0.8, "Active".to_string()),
-            BoundaryState::new("SD-CuD".to_string(), 0.5, "Active".to_string()),
-        ];
+        .bind(&anchor.anchor_type)
+        .bind(&anchor.description)
+        .bind(anchor.confidence)
+        .bind(domains_json)
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
 
-        let patterns = vec!["Pattern 1".to_string(), "Pattern 2".to_string()];
-        let user_input = "Sample user query for testing memory persistence";
+    /// Find a user's identity anchors whose type or description contains the query
+    pub async fn search_identity_anchors(
+        &self,
+        user_id: Uuid,
+        query: &str,
+    ) -> Result<Vec<FlowIdentityAnchor>, sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        // Match the query literally, not as a LIKE pattern
+        let escaped = query
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let pattern = format!("%{}%", escaped);
+        let rows = sqlx::query(
+            "SELECT anchor_type, description, confidence, domains
+             FROM identity_anchors
+             WHERE user_id = ? AND (description LIKE ? ESCAPE '\\' OR anchor_type LIKE ? ESCAPE '\\')
+             ORDER BY confidence DESC, created_at DESC",
+        )
+        .bind(user_id.as_bytes().to_vec())
+        .bind(&pattern)
+        .bind(&pattern)
+        .fetch_all(&self.db_pool)
+        .await?;
 
-        memory_manager
-            .create_snapshot(domains, boundaries, patterns, user_id, user_input)
-            .await
-            .unwrap();
+        rows.iter().map(Self::anchor_from_row).collect()
+    }
 
-        let latest_snapshot = memory_manager
-            .get_latest_snapshot(user_id)
-            .await
-            .unwrap()
-            .unwrap();
+    /// A user's `limit` most confident identity anchors
+    pub async fn get_top_identity_anchors(
+        &self,
+        user_id: Uuid,
+        limit: usize,
+    ) -> Result<Vec<FlowIdentityAnchor>, sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        let rows = sqlx::query(
+            "SELECT anchor_type, description, confidence, domains
+             FROM identity_anchors
+             WHERE user_id = ?
+             ORDER BY confidence DESC, created_at DESC
+             LIMIT ?",
+        )
+        .bind(user_id.as_bytes().to_vec())
+        .bind(limit as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
 
-        assert_eq!(latest_snapshot.domain_values.len(), 2);
-        assert_eq!(latest_snapshot.pattern_ids.len(), 2);
+        rows.iter().map(Self::anchor_from_row).collect()
     }
 
-    #[tokio::test]
-    async fn test_metadata_persistence_roundtrip() {
-        // Use in-memory database for testing
-        let db_pool = setup_test_db().await.unwrap();
-        let memory_manager = MemoryManager { db_pool };
+    /// Count an observation of a pattern, creating its record on first sight
+    #[cfg(test)]
+    pub async fn record_pattern(
+        &self,
+        user_id: Uuid,
+        description: &str,
+    ) -> Result<(), sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        Self::upsert_pattern(&self.db_pool, user_id, description).await
+    }
 
-        // Create a test user first
-        let user_id = Uuid::new_v4();
+    async fn upsert_pattern<'e, E>(
+        executor: E,
+        user_id: Uuid,
+        description: &str,
+    ) -> Result<(), sqlx::Error>
+    where
+        E: sqlx::SqliteExecutor<'e>,
+    {
+        let now = chrono::Utc::now().to_rfc3339();
         sqlx::query(
-            "INSERT INTO users (id, provider, provider_id, email, name, created_at, last_login)
-             VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))",
+            "INSERT INTO user_patterns (pattern_id, user_id, description, occurrence_count, first_seen, last_seen)
+             VALUES (?, ?, ?, 1, ?, ?)
+             ON CONFLICT(user_id, description) DO UPDATE SET
+                 occurrence_count = occurrence_count + 1,
+                 last_seen = excluded.last_seen",
         )
+        .bind(Uuid::new_v4().as_bytes().to_vec())
         .bind(user_id.as_bytes().to_vec())
-        .bind("test")
-        .bind(user_id.to_string())
-        .bind("test@example.com")
-        .bind("Test User")
-        .execute(&memory_manager.db_pool)
-        .await
-        .unwrap();
+        .bind(description)
+        .bind(&now)
+        .bind(&now)
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
 
-        // Create a snapshot with rich metadata (interface_states, qualities, developmental_stage)
-        let interface_states = vec![
-            CompactInterfaceState {
-                domains: ("COMP".to_string(), "SCI".to_string()),
-                permeability: 8,
-                flow_state: CompactInterfaceFlowState {
-                    invitation: "Explore computational rigor".to_string(),
-                    attention: "Focus on empirical validation".to_string(),
-                    resonance: 7,
-                    emergence: vec!["Pattern A".to_string(), "Pattern B".to_string()],
-                },
-            },
-            CompactInterfaceState {
-                domains: ("SCI".to_string(), "CULT".to_string()),
-                permeability: 6,
-                flow_state: CompactInterfaceFlowState {
-                    invitation: "Bridge data to narrative".to_string(),
-                    attention: "Context awareness".to_string(),
-                    resonance: 5,
-                    emergence: vec!["Pattern C".to_string()],
-                },
-            },
-        ];
+    /// Patterns seen at least `min_occurrences` times, most frequent first
+    pub async fn get_frequent_patterns(
+        &self,
+        user_id: Uuid,
+        min_occurrences: u32,
+    ) -> Result<Vec<PatternRecord>, sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        let rows = sqlx::query(
+            "SELECT pattern_id, description, occurrence_count, first_seen, last_seen
+             FROM user_patterns
+             WHERE user_id = ? AND occurrence_count >= ?
+             ORDER BY occurrence_count DESC, last_seen DESC",
+        )
+        .bind(user_id.as_bytes().to_vec())
+        .bind(min_occurrences)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let parse_time = |value: String| {
+            chrono::DateTime::parse_from_rfc3339(&value)
+                .map(|time| time.with_timezone(&chrono::Utc))
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+        };
+        rows.iter()
+            .map(|row| {
+                let pattern_id: Vec<u8> = row.get("pattern_id");
+                Ok(PatternRecord {
+                    pattern_id: Uuid::from_slice(&pattern_id)
+                        .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+                    user_id,
+                    description: row.get("description"),
+                    occurrence_count: row.get("occurrence_count"),
+                    first_seen: parse_time(row.get("first_seen"))?,
+                    last_seen: parse_time(row.get("last_seen"))?,
+                })
+            })
+            .collect()
+    }
 
-        let qualities = [8, 7, 6, 9, 7, 8, 8]; // clarity, depth, openness, precision, fluidity, resonance, coherence
-        let developmental_stage = 3; // Integration stage
+    /// Persist a user's framework state, replacing any previously saved state
+    pub async fn save_framework_state(
+        &self,
+        user_id: Uuid,
+        framework_state: &FrameworkState,
+    ) -> Result<(), sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        let state_json = serde_json::to_string(framework_state)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
 
-        let snapshot = CompactStateSnapshot {
-            id: Uuid::new_v4().to_string(),
-            timestamp: chrono::Utc::now().timestamp(),
-            user_id: user_id.to_string(),
-            domain_values: std::collections::HashMap::from([(1, vec![8, 7]), (2, vec![7, 8])]),
-            boundary_states: 0b1010101010,
+        sqlx::query(
+            "INSERT INTO framework_states (user_id, state, updated_at)
+             VALUES (?, ?, datetime('now'))
+             ON CONFLICT(user_id) DO UPDATE SET state = excluded.state, updated_at = excluded.updated_at",
+        )
+        .bind(user_id.as_bytes().to_vec())
+        .bind(state_json)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn load_framework_state(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<FrameworkState>, sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        let row = sqlx::query("SELECT state FROM framework_states WHERE user_id = ?")
+            .bind(user_id.as_bytes().to_vec())
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let state_json: String = row.get("state");
+        let mut framework_state: FrameworkState =
+            serde_json::from_str(&state_json).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        // Identity versions are stored separately and take precedence
+        framework_state.apply_identity_history(self.get_identity_history(user_id).await?);
+        Ok(Some(framework_state))
+    }
+
+    /// Append an identity version to a user's history
+    pub async fn save_identity_version(
+        &self,
+        user_id: Uuid,
+        version: &IdentityVersion,
+    ) -> Result<(), sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        sqlx::query(
+            "INSERT INTO identity_versions (user_id, version, identity, change_trigger, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(user_id.as_bytes().to_vec())
+        .bind(version.version)
+        .bind(&version.identity_string)
+        .bind(&version.trigger)
+        .bind(version.created_at.to_rfc3339())
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// A user's identity versions, oldest first
+    pub async fn get_identity_history(
+        &self,
+        user_id: Uuid,
+    ) -> Result<IdentityHistory, sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        let rows = sqlx::query(
+            "SELECT version, identity, change_trigger, created_at
+             FROM identity_versions
+             WHERE user_id = ?
+             ORDER BY version",
+        )
+        .bind(user_id.as_bytes().to_vec())
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let versions = rows
+            .iter()
+            .map(|row| {
+                let created_at: String = row.get("created_at");
+                Ok(IdentityVersion {
+                    version: row.get("version"),
+                    identity_string: row.get("identity"),
+                    trigger: row.get("change_trigger"),
+                    created_at: DateTime::parse_from_rfc3339(&created_at)
+                        .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+                        .with_timezone(&Utc),
+                })
+            })
+            .collect::<Result<_, sqlx::Error>>()?;
+        Ok(IdentityHistory { versions })
+    }
+
+    /// Persist a prototype's learned fitness, replacing any saved value
+    pub async fn save_prototype_fitness(
+        &self,
+        prototype: &PrototypeFitness,
+    ) -> Result<(), sqlx::Error> {
+        let history = serde_json::to_string(&prototype.history)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        sqlx::query(
+            "INSERT INTO prototype_fitness (prototype_name, fitness, history, updated_at)
+             VALUES (?, ?, ?, datetime('now'))
+             ON CONFLICT(prototype_name) DO UPDATE SET
+                fitness = excluded.fitness,
+                history = excluded.history,
+                updated_at = excluded.updated_at",
+        )
+        .bind(&prototype.name)
+        .bind(prototype.fitness)
+        .bind(history)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Every saved prototype fitness
+    pub async fn load_prototype_fitness(&self) -> Result<Vec<PrototypeFitness>, sqlx::Error> {
+        let rows = sqlx::query("SELECT prototype_name, fitness, history FROM prototype_fitness")
+            .fetch_all(&self.db_pool)
+            .await?;
+        rows.iter()
+            .map(|row| {
+                let history: String = row.get("history");
+                Ok(PrototypeFitness {
+                    name: row.get("prototype_name"),
+                    fitness: row.get("fitness"),
+                    history: serde_json::from_str(&history)
+                        .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn save_interface_rating(
+        &self,
+        user_id: Uuid,
+        rating: &InterfaceExperienceRating,
+    ) -> Result<(), sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        sqlx::query(
+            "INSERT INTO interface_experience_ratings (id, user_id, boundary_name, helpful, user_comment)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().as_bytes().to_vec())
+        .bind(user_id.as_bytes().to_vec())
+        .bind(&rating.boundary_name)
+        .bind(rating.helpful)
+        .bind(&rating.user_comment)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Erase a user's personal data while keeping the user row, so rows that
+    /// reference it stay valid. Clears the email and name, and deletes flow
+    /// executions, snapshots, anchors, patterns, ratings, the saved framework
+    /// state and the profile. Runs in one transaction.
+    pub async fn anonymize_user(&self, user_id: Uuid) -> Result<AnonymizationReport, sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        let user_id_bytes = user_id.as_bytes().to_vec();
+        let mut tx = self.db_pool.begin().await?;
+
+        // Executions belong to snapshots, so they could never outlive them
+        // in redacted form; delete them outright
+        let turns_deleted = sqlx::query("DELETE FROM flow_process_executions WHERE user_id = ?")
+            .bind(&user_id_bytes)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected() as u32;
+
+        // provider_id is replaced too, since it identifies the external account
+        sqlx::query(
+            "UPDATE users SET email = '[REDACTED]', name = NULL, provider_id = ? WHERE id = ?",
+        )
+        .bind(user_id.to_string())
+        .bind(&user_id_bytes)
+        .execute(&mut *tx)
+        .await?;
+
+        let snapshots_deleted = sqlx::query("DELETE FROM state_snapshots WHERE user_id = ?")
+            .bind(&user_id_bytes)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected() as u32;
+
+        for table in [
+            "identity_anchors",
+            "identity_versions",
+            "user_patterns",
+            "interface_experience_ratings",
+            "framework_states",
+            "user_profiles",
+        ] {
+            sqlx::query(&format!("DELETE FROM {} WHERE user_id = ?", table))
+                .bind(&user_id_bytes)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(AnonymizationReport {
+            turns_deleted,
+            snapshots_deleted,
+        })
+    }
+
+    /// Copy a user and all of their data into another database, e.g. when
+    /// promoting an account from dev to prod. The target must already be
+    /// migrated and must not contain the user; it is written in one transaction.
+    pub async fn transfer_user_data(
+        &self,
+        user_id: Uuid,
+        target_pool: &SqlitePool,
+    ) -> Result<TransferReport, sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        let user_id_bytes = user_id.as_bytes().to_vec();
+        let mut report = TransferReport {
+            snapshots_transferred: 0,
+            turns_transferred: 0,
+            rows_transferred: 0,
+        };
+        let mut tx = target_pool.begin().await?;
+
+        for (table, filter) in USER_DATA_TABLES {
+            // Only copy columns both schemas have, so a target missing a
+            // newer optional column still accepts the rows
+            let source_columns = Self::table_columns(&self.db_pool, table).await?;
+            let target_columns = Self::table_columns(&mut *tx, table).await?;
+            let columns: Vec<&String> = source_columns
+                .iter()
+                .filter(|column| target_columns.contains(column))
+                .collect();
+
+            // quote() renders each value as an SQL literal, preserving its type
+            let select = format!(
+                "SELECT {} FROM {} WHERE {}",
+                columns
+                    .iter()
+                    .map(|column| format!("quote({})", column))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                table,
+                filter
+            );
+            let rows = sqlx::query(&select)
+                .bind(&user_id_bytes)
+                .fetch_all(&self.db_pool)
+                .await?;
+
+            let column_list = columns
+                .iter()
+                .map(|column| column.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            for row in &rows {
+                let values = (0..columns.len())
+                    .map(|i| row.try_get::<String, _>(i))
+                    .collect::<Result<Vec<_>, _>>()?;
+                sqlx::query(&format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    table,
+                    column_list,
+                    values.join(", ")
+                ))
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            let copied = rows.len() as u32;
+            report.rows_transferred += copied;
+            match *table {
+                "state_snapshots" => report.snapshots_transferred = copied,
+                "flow_process_executions" => report.turns_transferred = copied,
+                _ => {}
+            }
+        }
+
+        tx.commit().await?;
+        Ok(report)
+    }
+
+    async fn table_columns<'e, E>(executor: E, table: &str) -> Result<Vec<String>, sqlx::Error>
+    where
+        E: sqlx::SqliteExecutor<'e>,
+    {
+        let rows = sqlx::query(&format!("PRAGMA table_info({})", table))
+            .fetch_all(executor)
+            .await?;
+        Ok(rows.iter().map(|row| row.get("name")).collect())
+    }
+
+    /// Share of helpful ratings per boundary, for boundaries with enough ratings
+    pub async fn get_boundary_ratings(
+        &self,
+        user_id: Uuid,
+    ) -> Result<HashMap<String, f64>, sqlx::Error> {
+        self.ensure_in_organization(user_id).await?;
+        let rows = sqlx::query(
+            "SELECT boundary_name, AVG(helpful) AS helpful_share
+             FROM interface_experience_ratings
+             WHERE user_id = ?
+             GROUP BY boundary_name
+             HAVING COUNT(*) >= ?",
+        )
+        .bind(user_id.as_bytes().to_vec())
+        .bind(MIN_RATINGS_FOR_AVERAGE)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("boundary_name"), row.get("helpful_share")))
+            .collect())
+    }
+
+    fn anchor_from_row(row: &SqliteRow) -> Result<FlowIdentityAnchor, sqlx::Error> {
+        let domains_json: String = row.get("domains");
+        let domains: Vec<String> =
+            serde_json::from_str(&domains_json).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        Ok(FlowIdentityAnchor {
+            anchor_type: row.get("anchor_type"),
+            description: row.get("description"),
+            confidence: row.get("confidence"),
+            domains,
+        })
+    }
+
+    fn snapshot_from_row(row: &SqliteRow) -> Result<CompactStateSnapshot, sqlx::Error> {
+        // Deserialize from separate columns
+        let id: Vec<u8> = row.get("id");
+        let user_id_bytes: Vec<u8> = row.get("user_id");
+        let timestamp_str: String = row.get("timestamp");
+        let domain_states_json: String = row.get("domain_states");
+        let boundary_states_json: String = row.get("boundary_states");
+        let pattern_ids_json: String = row.get("pattern_ids");
+        let identity_anchors_json: String = row.get("identity_anchors");
+        let metadata_json: Option<String> = row.get("metadata");
+
+        let id_uuid = Uuid::from_slice(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let user_id_uuid =
+            Uuid::from_slice(&user_id_bytes).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&timestamp_str)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+            .timestamp();
+
+        let domain_values: HashMap<u8, Vec<u8>> = serde_json::from_str(&domain_states_json)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let boundary_states: u64 = serde_json::from_str(&boundary_states_json)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let pattern_ids: Vec<String> = serde_json::from_str(&pattern_ids_json)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let identity_anchor_ids: Vec<String> = serde_json::from_str(&identity_anchors_json)
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        // Deserialize metadata (interface_states, qualities, developmental_stage)
+        // Default to empty/zero if metadata column is null (backward compatibility)
+        let metadata = if let Some(json) = metadata_json {
+            serde_json::from_str::<SnapshotMetadata>(&json)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
+        } else {
+            SnapshotMetadata {
+                interface_states: vec![],
+                qualities: [0; 7],
+                developmental_stage: 0,
+                aggregate_quality: None,
+            }
+        };
+
+        Ok(CompactStateSnapshot {
+            id: id_uuid.to_string(),
+            timestamp,
+            user_id: user_id_uuid.to_string(),
+            domain_values,
+            boundary_states,
+            interface_states: metadata.interface_states,
+            qualities: metadata.qualities,
+            aggregate_quality: metadata.aggregate_quality,
+            identity_anchor_ids,
+            pattern_ids,
+            developmental_stage: metadata.developmental_stage,
+        })
+    }
+}
+
+/// Memory writes that are committed together or not at all.
+/// Dropping the transaction without committing rolls it back.
+pub struct MemoryTransaction {
+    manager: MemoryManager,
+    tx: Transaction<'static, Sqlite>,
+}
+
+impl MemoryTransaction {
+    /// Transactional counterpart of `MemoryManager::create_snapshot`
+    pub async fn save_snapshot(
+        &mut self,
+        domains: Vec<DomainState>,
+        boundaries: Vec<BoundaryState>,
+        patterns: Vec<String>,
+        user_id: Uuid,
+        user_input: &str,
+        aggregate_quality: Option<&PhenomenologicalQuality>,
+    ) -> Result<(), sqlx::Error> {
+        self.manager
+            .ensure_in_organization_with(&mut *self.tx, user_id)
+            .await?;
+        let compact_snapshot = self.manager.compress_snapshot(
+            domains,
+            boundaries,
+            patterns,
+            user_id,
+            user_input,
+            aggregate_quality,
+        );
+        MemoryManager::insert_snapshot(&mut *self.tx, &compact_snapshot).await
+    }
+
+    /// Transactional counterpart of `MemoryManager::save_identity_anchor`
+    pub async fn save_anchor(
+        &mut self,
+        user_id: Uuid,
+        anchor: &FlowIdentityAnchor,
+    ) -> Result<(), sqlx::Error> {
+        self.manager
+            .ensure_in_organization_with(&mut *self.tx, user_id)
+            .await?;
+        MemoryManager::insert_identity_anchor(&mut *self.tx, user_id, anchor).await
+    }
+
+    /// Transactional counterpart of `MemoryManager::record_pattern`
+    pub async fn record_pattern(
+        &mut self,
+        user_id: Uuid,
+        description: &str,
+    ) -> Result<(), sqlx::Error> {
+        self.manager
+            .ensure_in_organization_with(&mut *self.tx, user_id)
+            .await?;
+        MemoryManager::upsert_pattern(&mut *self.tx, user_id, description).await
+    }
+
+    pub async fn commit(self) -> Result<(), sqlx::Error> {
+        self.tx.commit().await
+    }
+
+    pub async fn rollback(self) -> Result<(), sqlx::Error> {
+        self.tx.rollback().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt_engine::{BoundaryState, DomainState};
+    use crate::test_utils::{insert_test_user, setup_test_db};
+
+    #[tokio::test]
+    async fn test_memory_manager() {
+        // Use in-memory database for testing
+        let db_pool = setup_test_db().await.unwrap();
+        let memory_manager = MemoryManager {
+            db_pool,
+            organization_id: None,
+        };
+
+        // Create a test user first (required by foreign key constraint)
+        let user_id = Uuid::new_v4();
+        insert_test_user(&memory_manager.db_pool, user_id).await;
+
+        let domains = vec![
+            DomainState {
+                name: "CD".to_string(),
+                state: "Computational Domain State".to_string(),
+            },
+            DomainState {
+                name: "SD".to_string(),
+                state: "Scientific Domain State".to_string(),
+            },
+        ];
+
+        let boundaries = vec![
+            BoundaryState::new("CD-SD".to_string(), 0.8, "Active".to_string()),
+            BoundaryState::new("SD-CuD".to_string(), 0.5, "Active".to_string()),
+        ];
+
+        let patterns = vec!["Pattern 1".to_string(), "Pattern 2".to_string()];
+        let user_input = "Sample user query for testing memory persistence";
+
+        memory_manager
+            .create_snapshot(domains, boundaries, patterns, user_id, user_input, None)
+            .await
+            .unwrap();
+
+        let latest_snapshot = memory_manager
+            .get_latest_snapshot(user_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(latest_snapshot.domain_values.len(), 2);
+        assert_eq!(latest_snapshot.pattern_ids.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_persistence_roundtrip() {
+        // Use in-memory database for testing
+        let db_pool = setup_test_db().await.unwrap();
+        let memory_manager = MemoryManager {
+            db_pool,
+            organization_id: None,
+        };
+
+        // Create a test user first
+        let user_id = Uuid::new_v4();
+        insert_test_user(&memory_manager.db_pool, user_id).await;
+
+        // Create a snapshot with rich metadata (interface_states, qualities, developmental_stage)
+        let interface_states = vec![
+            CompactInterfaceState {
+                domains: ("COMP".to_string(), "SCI".to_string()),
+                permeability: 8,
+                flow_state: CompactInterfaceFlowState {
+                    invitation: "Explore computational rigor".to_string(),
+                    attention: "Focus on empirical validation".to_string(),
+                    resonance: 7,
+                    emergence: vec!["Pattern A".to_string(), "Pattern B".to_string()],
+                },
+            },
+            CompactInterfaceState {
+                domains: ("SCI".to_string(), "CULT".to_string()),
+                permeability: 6,
+                flow_state: CompactInterfaceFlowState {
+                    invitation: "Bridge data to narrative".to_string(),
+                    attention: "Context awareness".to_string(),
+                    resonance: 5,
+                    emergence: vec!["Pattern C".to_string()],
+                },
+            },
+        ];
+
+        let qualities = [8, 7, 6, 9, 7, 8, 8]; // clarity, depth, openness, precision, fluidity, resonance, coherence
+        let developmental_stage = 3; // Integration stage
+
+        let snapshot = CompactStateSnapshot {
+            id: Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            user_id: user_id.to_string(),
+            domain_values: std::collections::HashMap::from([(1, vec![8, 7]), (2, vec![7, 8])]),
+            boundary_states: 0b1010101010,
             interface_states: interface_states.clone(),
             qualities,
+            aggregate_quality: Some([200, 180, 160, 140, 120, 100, 80]),
             identity_anchor_ids: vec!["anchor1".to_string(), "anchor2".to_string()],
             pattern_ids: vec!["pattern1".to_string()],
             developmental_stage,
         };
 
         // Save snapshot
-        memory_manager.save_snapshot_to_db(&snapshot).await.unwrap();
+        MemoryManager::insert_snapshot(&memory_manager.db_pool, &snapshot)
+            .await
+            .unwrap();
 
         // Retrieve snapshot
         let retrieved = memory_manager
@@ -594,6 +1608,11 @@ mod tests {
             retrieved.developmental_stage, 3,
             "developmental_stage should persist"
         );
+        assert_eq!(
+            retrieved.aggregate_quality,
+            Some([200, 180, 160, 140, 120, 100, 80]),
+            "aggregate_quality should persist"
+        );
 
         // Also verify basic fields still work
         assert_eq!(retrieved.domain_values.len(), 2);
@@ -601,6 +1620,300 @@ mod tests {
         assert_eq!(retrieved.pattern_ids.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_single_connection_pool_handles_concurrent_queries() {
+        let config = DatabaseConfig {
+            max_connections: 1,
+            min_connections: 1,
+            query_timeout: Duration::from_secs(5),
+            idle_timeout: Duration::from_secs(60),
+        };
+        let memory_manager = MemoryManager::from_config("sqlite::memory:", config)
+            .await
+            .unwrap();
+
+        let handles: Vec<_> = (0..10)
+            .map(|i| {
+                let pool = memory_manager.db_pool.clone();
+                tokio::spawn(async move {
+                    sqlx::query("SELECT ?")
+                        .bind(i as i64)
+                        .fetch_one(&pool)
+                        .await
+                        .map(|row| row.get::<i64, _>(0))
+                })
+            })
+            .collect();
+
+        let results = tokio::time::timeout(Duration::from_secs(10), async {
+            let mut values = Vec::new();
+            for handle in handles {
+                values.push(handle.await.unwrap().unwrap());
+            }
+            values
+        })
+        .await
+        .expect("queries on a single-connection pool should not deadlock");
+
+        assert_eq!(results, (0..10).collect::<Vec<i64>>());
+    }
+
+    #[test]
+    fn test_database_config_from_env_falls_back_to_defaults() {
+        std::env::remove_var("DATABASE_MAX_CONNECTIONS");
+        std::env::remove_var("DATABASE_MIN_CONNECTIONS");
+        std::env::remove_var("DATABASE_QUERY_TIMEOUT_MS");
+        std::env::remove_var("DATABASE_IDLE_TIMEOUT_SECS");
+        assert_eq!(DatabaseConfig::from_env(), DatabaseConfig::default());
+    }
+
+    #[tokio::test]
+    async fn test_anonymize_user() {
+        let db_pool = setup_test_db().await.unwrap();
+        let memory_manager = MemoryManager {
+            db_pool,
+            organization_id: None,
+        };
+
+        let user_id = Uuid::new_v4();
+        insert_test_user(&memory_manager.db_pool, user_id).await;
+
+        for i in 0..2 {
+            memory_manager
+                .create_snapshot(
+                    vec![],
+                    vec![],
+                    vec![],
+                    user_id,
+                    &format!("private question {}", i),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        let latest = memory_manager
+            .get_latest_snapshot(user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let snapshot_id = Uuid::parse_str(latest.id()).unwrap();
+        for i in 0..3 {
+            sqlx::query(
+                "INSERT INTO flow_process_executions (id, user_id, snapshot_id, input_text, output_text)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4().as_bytes().to_vec())
+            .bind(user_id.as_bytes().to_vec())
+            .bind(snapshot_id.as_bytes().to_vec())
+            .bind(format!("private question {}", i))
+            .bind("private answer")
+            .execute(&memory_manager.db_pool)
+            .await
+            .unwrap();
+        }
+        memory_manager
+            .record_pattern(user_id, "Cross-domain integration: CD, SD")
+            .await
+            .unwrap();
+
+        let report = memory_manager.anonymize_user(user_id).await.unwrap();
+        assert_eq!(
+            report,
+            AnonymizationReport {
+                turns_deleted: 3,
+                snapshots_deleted: 2,
+            }
+        );
+
+        // The user row survives without its personal details
+        let row = sqlx::query("SELECT email, name, provider_id FROM users WHERE id = ?")
+            .bind(user_id.as_bytes().to_vec())
+            .fetch_one(&memory_manager.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, _>("email"), "[REDACTED]");
+        assert_eq!(row.get::<Option<String>, _>("name"), None);
+        assert_eq!(row.get::<String, _>("provider_id"), user_id.to_string());
+
+        // None of the user's turns remain
+        let (remaining,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM flow_process_executions WHERE user_id = ?")
+                .bind(user_id.as_bytes().to_vec())
+                .fetch_one(&memory_manager.db_pool)
+                .await
+                .unwrap();
+        assert_eq!(remaining, 0);
+        assert!(memory_manager
+            .get_latest_snapshot(user_id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(memory_manager
+            .get_frequent_patterns(user_id, 1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recurring_patterns_are_counted() {
+        let db_pool = setup_test_db().await.unwrap();
+        let memory_manager = MemoryManager {
+            db_pool,
+            organization_id: None,
+        };
+
+        let user_id = Uuid::new_v4();
+        insert_test_user(&memory_manager.db_pool, user_id).await;
+
+        // The same pattern shows up in three separate requests
+        let recurring = "Cross-domain integration: CD, SD";
+        for _ in 0..3 {
+            memory_manager
+                .record_pattern(user_id, recurring)
+                .await
+                .unwrap();
+        }
+        memory_manager
+            .record_pattern(user_id, "Cross-domain integration: CuD, ED")
+            .await
+            .unwrap();
+
+        let frequent = memory_manager
+            .get_frequent_patterns(user_id, 2)
+            .await
+            .unwrap();
+        assert_eq!(frequent.len(), 1);
+        assert_eq!(frequent[0].description, recurring);
+        assert_eq!(frequent[0].occurrence_count, 3);
+        assert!(frequent[0].first_seen <= frequent[0].last_seen);
+
+        let all = memory_manager
+            .get_frequent_patterns(user_id, 1)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].description, recurring);
+    }
+
+    #[tokio::test]
+    async fn test_identity_anchor_search() {
+        let db_pool = setup_test_db().await.unwrap();
+        let memory_manager = MemoryManager {
+            db_pool,
+            organization_id: None,
+        };
+
+        let user_id = Uuid::new_v4();
+        insert_test_user(&memory_manager.db_pool, user_id).await;
+
+        let anchors = [
+            FlowIdentityAnchor {
+                anchor_type: "Computational".to_string(),
+                description: "Strong analytical reasoning".to_string(),
+                confidence: 0.8,
+                domains: vec!["CD".to_string()],
+            },
+            FlowIdentityAnchor {
+                anchor_type: "Experiential".to_string(),
+                description: "Attentive to lived experience".to_string(),
+                confidence: 0.7,
+                domains: vec!["ED".to_string(), "CuD".to_string()],
+            },
+        ];
+        for anchor in &anchors {
+            memory_manager
+                .save_identity_anchor(user_id, anchor)
+                .await
+                .unwrap();
+        }
+
+        let results = memory_manager
+            .search_identity_anchors(user_id, "analytical")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].anchor_type, "Computational");
+        assert_eq!(results[0].domains, vec!["CD".to_string()]);
+
+        // Saving an anchor again updates it rather than adding a row
+        let updated = FlowIdentityAnchor {
+            confidence: 0.9,
+            ..anchors[0].clone()
+        };
+        memory_manager
+            .save_identity_anchor(user_id, &updated)
+            .await
+            .unwrap();
+        let results = memory_manager
+            .search_identity_anchors(user_id, "analytical")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].confidence, 0.9);
+
+        // Wildcards in the query match literally
+        for query in ["%", "_", "lived_experience"] {
+            assert!(memory_manager
+                .search_identity_anchors(user_id, query)
+                .await
+                .unwrap()
+                .is_empty());
+        }
+
+        // Anchors are scoped to their owner
+        let results = memory_manager
+            .search_identity_anchors(Uuid::new_v4(), "analytical")
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let interface_state = |permeability: u8| CompactInterfaceState {
+            domains: ("CD".to_string(), "SD".to_string()),
+            permeability,
+            flow_state: CompactInterfaceFlowState {
+                invitation: String::new(),
+                attention: String::new(),
+                resonance: 0,
+                emergence: vec![],
+            },
+        };
+        let snapshot =
+            |cd: u8, sd: u8, permeability: u8, clarity: u8, stage: u8| CompactStateSnapshot {
+                id: Uuid::new_v4().to_string(),
+                timestamp: chrono::Utc::now().timestamp(),
+                user_id: Uuid::new_v4().to_string(),
+                domain_values: HashMap::from([(0, vec![cd]), (1, vec![sd])]),
+                boundary_states: 0,
+                interface_states: vec![interface_state(permeability)],
+                qualities: [clarity, 0, 0, 0, 0, 0, 0],
+                aggregate_quality: None,
+                identity_anchor_ids: vec![],
+                pattern_ids: vec![],
+                developmental_stage: stage,
+            };
+
+        let before = snapshot(40, 70, 51, 0, 1);
+        let after = snapshot(90, 70, 102, 255, 2);
+
+        let diff = before.diff(&after);
+
+        assert!((diff.domain_changes["CD"] - 0.5).abs() < 1e-9);
+        assert!(diff.domain_changes["SD"].abs() < 1e-9);
+        assert!((diff.boundary_permeability_changes["CD-SD"] - 0.2).abs() < 1e-9);
+        assert!((diff.quality_deltas[0] - 1.0).abs() < 1e-9);
+        assert!(diff.developmental_stage_changed);
+        assert!((diff.max_domain_change() - 0.5).abs() < 1e-9);
+
+        // Identical snapshots produce no drift
+        let unchanged = before.diff(&before);
+        assert_eq!(unchanged.max_domain_change(), 0.0);
+        assert!(!unchanged.developmental_stage_changed);
+    }
+
     #[tokio::test]
     async fn test_metadata_corruption_handling() {
         // Test that corrupted/malformed metadata doesn't crash the system
@@ -608,22 +1921,12 @@ mod tests {
         let db_pool = setup_test_db().await.unwrap();
         let memory_manager = MemoryManager {
             db_pool: db_pool.clone(),
+            organization_id: None,
         };
 
         // Create a test user
         let user_id = Uuid::new_v4();
-        sqlx::query(
-            "INSERT INTO users (id, provider, provider_id, email, name, created_at, last_login)
-             VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))",
-        )
-        .bind(user_id.as_bytes().to_vec())
-        .bind("test")
-        .bind(user_id.to_string())
-        .bind("test@example.com")
-        .bind("Test User")
-        .execute(&db_pool)
-        .await
-        .unwrap();
+        insert_test_user(&db_pool, user_id).await;
 
         // Manually insert a snapshot with corrupted JSON metadata
         let snapshot_id = Uuid::new_v4();
@@ -752,18 +2055,7 @@ mod tests {
 
         // Now verify that with a valid user, insert succeeds
         let valid_user_id = Uuid::new_v4();
-        sqlx::query(
-            "INSERT INTO users (id, provider, provider_id, email, name, created_at, last_login)
-             VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))",
-        )
-        .bind(valid_user_id.as_bytes().to_vec())
-        .bind("test")
-        .bind(valid_user_id.to_string())
-        .bind("test@example.com")
-        .bind("Test User")
-        .execute(&db_pool)
-        .await
-        .expect("Should create test user");
+        insert_test_user(&db_pool, valid_user_id).await;
 
         // Now snapshot insert should succeed
         let result = sqlx::query(
@@ -798,21 +2090,11 @@ mod tests {
 
         // Create a test user
         let user_id = Uuid::new_v4();
-        sqlx::query(
-            "INSERT INTO users (id, provider, provider_id, email, name, created_at, last_login)
-             VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))",
-        )
-        .bind(user_id.as_bytes().to_vec())
-        .bind("test")
-        .bind(user_id.to_string())
-        .bind("test@example.com")
-        .bind("Test User")
-        .execute(&db_pool)
-        .await
-        .expect("Should create test user");
+        insert_test_user(&db_pool, user_id).await;
 
         let manager = MemoryManager {
             db_pool: db_pool.clone(),
+            organization_id: None,
         };
 
         // Spawn multiple concurrent tasks that read and write snapshots
@@ -821,6 +2103,7 @@ mod tests {
         for i in 0..10 {
             let manager_clone = MemoryManager {
                 db_pool: db_pool.clone(),
+                organization_id: None,
             };
             let user_id_clone = user_id;
 
@@ -848,6 +2131,7 @@ mod tests {
                         patterns,
                         user_id_clone,
                         &format!("test input {}", i),
+                        None,
                     )
                     .await
                     .expect("Should save snapshot");
@@ -888,4 +2172,449 @@ mod tests {
             "Should have at least one snapshot after concurrent operations"
         );
     }
+
+    #[tokio::test]
+    async fn test_organization_scope_isolates_users() {
+        let db_pool = setup_test_db().await.unwrap();
+        let memory_manager = MemoryManager {
+            db_pool,
+            organization_id: None,
+        };
+
+        let org_a = Uuid::new_v4();
+        let org_b = Uuid::new_v4();
+        let mut users = Vec::new();
+        for (org, email) in [(org_a, "a@example.com"), (org_b, "b@example.com")] {
+            let user_id = Uuid::new_v4();
+            insert_test_user(&memory_manager.db_pool, user_id).await;
+            memory_manager
+                .set_user_organization(user_id, org)
+                .await
+                .unwrap();
+            memory_manager
+                .create_snapshot(vec![], vec![], vec![], user_id, email, None)
+                .await
+                .unwrap();
+            users.push(user_id);
+        }
+        let (user_a, user_b) = (users[0], users[1]);
+
+        let scoped_a = memory_manager.with_organization(org_a);
+        let snapshot = scoped_a.get_latest_snapshot(user_a).await.unwrap();
+        assert_eq!(snapshot.unwrap().user_id, user_a.to_string());
+        scoped_a
+            .create_snapshot(vec![], vec![], vec![], user_a, "second", None)
+            .await
+            .unwrap();
+
+        // Org B's user is invisible to org A for reads and writes
+        assert!(matches!(
+            scoped_a.get_latest_snapshot(user_b).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        assert!(matches!(
+            scoped_a
+                .create_snapshot(vec![], vec![], vec![], user_b, "intrusion", None)
+                .await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        assert!(matches!(
+            scoped_a.set_user_organization(user_b, org_a).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        assert_eq!(
+            memory_manager
+                .get_recent_snapshots(user_b, 10)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // The unscoped manager still sees everyone
+        assert!(memory_manager
+            .get_latest_snapshot(user_b)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_paginate_snapshots() {
+        let db_pool = setup_test_db().await.unwrap();
+        let memory_manager = MemoryManager {
+            db_pool,
+            organization_id: None,
+        };
+        let user_id = Uuid::new_v4();
+        insert_test_user(&memory_manager.db_pool, user_id).await;
+
+        // One snapshot per minute so each has a distinct timestamp
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let mut ids = Vec::new();
+        for i in 0..10 {
+            let snapshot_id = Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO state_snapshots (id, user_id, timestamp, domain_states, boundary_states, pattern_ids, identity_anchors)
+                 VALUES (?, ?, ?, '{}', '0', '[]', '[]')",
+            )
+            .bind(snapshot_id.as_bytes().to_vec())
+            .bind(user_id.as_bytes().to_vec())
+            .bind((start + chrono::Duration::minutes(i)).to_rfc3339())
+            .execute(&memory_manager.db_pool)
+            .await
+            .unwrap();
+            ids.push(snapshot_id.to_string());
+        }
+
+        let first_page = memory_manager
+            .get_snapshots(user_id, 5, None)
+            .await
+            .unwrap();
+        let cursor =
+            |page: &[CompactStateSnapshot]| Uuid::parse_str(page.last().unwrap().id()).ok();
+        let second_page = memory_manager
+            .get_snapshots(user_id, 5, cursor(&first_page))
+            .await
+            .unwrap();
+        let third_page = memory_manager
+            .get_snapshots(user_id, 5, cursor(&second_page))
+            .await
+            .unwrap();
+        assert_eq!(first_page.len(), 5);
+        assert_eq!(second_page.len(), 5);
+        assert!(third_page.is_empty());
+
+        // Pages walk backwards in time; reversed they are chronological
+        let mut paged: Vec<String> = first_page
+            .iter()
+            .chain(second_page.iter())
+            .map(|snapshot| snapshot.id().to_string())
+            .collect();
+        paged.reverse();
+        assert_eq!(paged, ids);
+
+        let by_id = memory_manager
+            .get_snapshot_by_id(Uuid::parse_str(&ids[3]).unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(by_id.id(), ids[3]);
+        assert!(memory_manager
+            .get_snapshot_by_id(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+        assert!(memory_manager
+            .with_organization(Uuid::new_v4())
+            .get_snapshot_by_id(Uuid::parse_str(&ids[3]).unwrap())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_paginate_snapshots_sharing_a_timestamp() {
+        let db_pool = setup_test_db().await.unwrap();
+        let memory_manager = MemoryManager {
+            db_pool,
+            organization_id: None,
+        };
+        let user_id = Uuid::new_v4();
+        insert_test_user(&memory_manager.db_pool, user_id).await;
+
+        // Seven snapshots in the same instant, paged three at a time
+        let timestamp = Utc::now().to_rfc3339();
+        let mut ids = Vec::new();
+        for _ in 0..7 {
+            let snapshot_id = Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO state_snapshots (id, user_id, timestamp, domain_states, boundary_states, pattern_ids, identity_anchors)
+                 VALUES (?, ?, ?, '{}', '0', '[]', '[]')",
+            )
+            .bind(snapshot_id.as_bytes().to_vec())
+            .bind(user_id.as_bytes().to_vec())
+            .bind(&timestamp)
+            .execute(&memory_manager.db_pool)
+            .await
+            .unwrap();
+            ids.push(snapshot_id);
+        }
+
+        let mut paged = Vec::new();
+        let mut before = None;
+        loop {
+            let page = memory_manager
+                .get_snapshots(user_id, 3, before)
+                .await
+                .unwrap();
+            if page.is_empty() {
+                break;
+            }
+            assert!(page.len() <= 3);
+            paged.extend(page.iter().map(|s| Uuid::parse_str(s.id()).unwrap()));
+            before = paged.last().copied();
+        }
+
+        // Every snapshot exactly once, in descending id order within the instant
+        ids.sort();
+        ids.reverse();
+        assert_eq!(paged, ids);
+
+        assert!(memory_manager
+            .get_snapshots(user_id, 3, Some(Uuid::new_v4()))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_user_data() {
+        let source = MemoryManager {
+            db_pool: setup_test_db().await.unwrap(),
+            organization_id: None,
+        };
+        let target = setup_test_db().await.unwrap();
+
+        let user_id = Uuid::new_v4();
+        insert_test_user(&source.db_pool, user_id).await;
+
+        for i in 0..2 {
+            source
+                .create_snapshot(vec![], vec![], vec![], user_id, &format!("q{}", i), None)
+                .await
+                .unwrap();
+        }
+        let snapshot_id = Uuid::parse_str(
+            source
+                .get_latest_snapshot(user_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .id(),
+        )
+        .unwrap();
+        for i in 0..5 {
+            sqlx::query(
+                "INSERT INTO flow_process_executions (id, user_id, snapshot_id, input_text, output_text)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4().as_bytes().to_vec())
+            .bind(user_id.as_bytes().to_vec())
+            .bind(snapshot_id.as_bytes().to_vec())
+            .bind(format!("question {}", i))
+            .bind(format!("answer {}", i))
+            .execute(&source.db_pool)
+            .await
+            .unwrap();
+        }
+        source
+            .record_pattern(user_id, "Cross-domain integration: CD, SD")
+            .await
+            .unwrap();
+
+        let report = source.transfer_user_data(user_id, &target).await.unwrap();
+        assert_eq!(report.snapshots_transferred, 2);
+        assert_eq!(report.turns_transferred, 5);
+        // user + pattern + 2 snapshots + 5 executions
+        assert_eq!(report.rows_transferred, 9);
+
+        let target_manager = MemoryManager {
+            db_pool: target.clone(),
+            organization_id: None,
+        };
+        assert_eq!(
+            target_manager
+                .get_recent_snapshots(user_id, 10)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        let outputs: Vec<String> = sqlx::query_scalar(
+            "SELECT output_text FROM flow_process_executions WHERE user_id = ? ORDER BY input_text",
+        )
+        .bind(user_id.as_bytes().to_vec())
+        .fetch_all(&target)
+        .await
+        .unwrap();
+        assert_eq!(outputs.len(), 5);
+        assert_eq!(outputs[0], "answer 0");
+        assert_eq!(
+            target_manager
+                .get_frequent_patterns(user_id, 1)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // A second transfer conflicts and leaves the target untouched
+        assert!(source.transfer_user_data(user_id, &target).await.is_err());
+        let executions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM flow_process_executions")
+            .fetch_one(&target)
+            .await
+            .unwrap();
+        assert_eq!(executions, 5);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_user_data_to_single_connection_pool() {
+        let source = MemoryManager {
+            db_pool: setup_test_db().await.unwrap(),
+            organization_id: None,
+        };
+        // Every target query must go through the open transaction's connection
+        let target = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&target).await.unwrap();
+
+        let user_id = Uuid::new_v4();
+        insert_test_user(&source.db_pool, user_id).await;
+        source
+            .create_snapshot(vec![], vec![], vec![], user_id, "q", None)
+            .await
+            .unwrap();
+
+        let report = tokio::time::timeout(
+            Duration::from_secs(5),
+            source.transfer_user_data(user_id, &target),
+        )
+        .await
+        .expect("transfer should not wait on a second target connection")
+        .unwrap();
+        assert_eq!(report.snapshots_transferred, 1);
+        // user + snapshot
+        assert_eq!(report.rows_transferred, 2);
+    }
+
+    #[tokio::test]
+    async fn test_memory_transaction_is_atomic() {
+        let memory_manager = MemoryManager {
+            db_pool: setup_test_db().await.unwrap(),
+            organization_id: None,
+        };
+        let user_id = Uuid::new_v4();
+        insert_test_user(&memory_manager.db_pool, user_id).await;
+
+        let anchor = FlowIdentityAnchor {
+            anchor_type: "Computational".to_string(),
+            description: "Prefers worked examples".to_string(),
+            confidence: 0.8,
+            domains: vec!["CD".to_string()],
+        };
+        let count_rows = |table: &'static str| {
+            let pool = memory_manager.db_pool.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {}", table))
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Explicit rollback discards every write
+        let mut tx = memory_manager.begin_transaction().await.unwrap();
+        tx.save_snapshot(vec![], vec![], vec![], user_id, "rolled back", None)
+            .await
+            .unwrap();
+        tx.save_anchor(user_id, &anchor).await.unwrap();
+        tx.rollback().await.unwrap();
+        assert_eq!(count_rows("state_snapshots").await, 0);
+        assert_eq!(count_rows("identity_anchors").await, 0);
+
+        // A panic after the first write drops the transaction, rolling it back
+        let manager = memory_manager.clone();
+        let outcome = tokio::spawn(async move {
+            let mut tx = manager.begin_transaction().await.unwrap();
+            tx.save_snapshot(vec![], vec![], vec![], user_id, "panicked", None)
+                .await
+                .unwrap();
+            panic!("second operation failed");
+        })
+        .await;
+        assert!(outcome.unwrap_err().is_panic());
+        assert_eq!(count_rows("state_snapshots").await, 0);
+
+        // Committed writes land together
+        let mut tx = memory_manager.begin_transaction().await.unwrap();
+        tx.save_snapshot(vec![], vec![], vec![], user_id, "committed", None)
+            .await
+            .unwrap();
+        tx.save_anchor(user_id, &anchor).await.unwrap();
+        tx.record_pattern(user_id, "Cross-domain integration: CD, SD")
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(count_rows("state_snapshots").await, 1);
+        assert_eq!(count_rows("identity_anchors").await, 1);
+        assert_eq!(count_rows("user_patterns").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_domain_history_is_chronological() {
+        let memory_manager = MemoryManager {
+            db_pool: setup_test_db().await.unwrap(),
+            organization_id: None,
+        };
+        let user_id = Uuid::new_v4();
+        insert_test_user(&memory_manager.db_pool, user_id).await;
+
+        // CD is key 0, SD is key 1; the second snapshot has no CD activation
+        let start = chrono::Utc::now().timestamp() - 3600;
+        let domain_values = [
+            HashMap::from([(0, vec![20])]),
+            HashMap::from([(1, vec![90])]),
+            HashMap::from([(0, vec![50]), (1, vec![10])]),
+            HashMap::from([(0, vec![80])]),
+        ];
+        let mut ids = Vec::new();
+        for (i, domain_values) in domain_values.into_iter().enumerate() {
+            let snapshot = CompactStateSnapshot {
+                id: Uuid::new_v4().to_string(),
+                timestamp: start + i as i64 * 60,
+                user_id: user_id.to_string(),
+                domain_values,
+                boundary_states: 0,
+                interface_states: vec![],
+                qualities: [0; 7],
+                aggregate_quality: None,
+                identity_anchor_ids: vec![],
+                pattern_ids: vec![],
+                developmental_stage: 0,
+            };
+            MemoryManager::insert_snapshot(&memory_manager.db_pool, &snapshot)
+                .await
+                .unwrap();
+            ids.push(Uuid::parse_str(&snapshot.id).unwrap());
+        }
+
+        let history = memory_manager
+            .get_domain_history(user_id, "CD", 10)
+            .await
+            .unwrap();
+        let activations: Vec<f64> = history.iter().map(|point| point.activation).collect();
+        assert_eq!(activations, vec![0.2, 0.5, 0.8]);
+        assert_eq!(history[0].snapshot_id, ids[0]);
+        assert_eq!(history[2].snapshot_id, ids[3]);
+        assert_eq!(history[0].timestamp.timestamp(), start);
+        assert!(history.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+
+        // The limit counts the most recent snapshots
+        let recent = memory_manager
+            .get_domain_history(user_id, "CD", 2)
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].activation, 0.5);
+
+        assert!(memory_manager
+            .get_domain_history(user_id, "ED", 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
 }
@@ -4,6 +4,7 @@
 use crate::llm_error::LlmError;
 use crate::LlmProvider;
 use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
 
 /// Mock LLM that returns deterministic responses for testing
 pub struct MockLlm {
@@ -49,6 +50,29 @@ impl MockLlm {
     }
 }
 
+/// Mock LLM that records every prompt it is sent.
+/// Clones share the same log, so keep a clone before boxing the provider.
+#[derive(Clone)]
+pub struct RecordingMockLlm {
+    responses: Vec<String>,
+    sent_prompts: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingMockLlm {
+    /// Create a recording mock with predetermined responses (echoes if empty)
+    pub fn new(responses: Vec<String>) -> Self {
+        Self {
+            responses,
+            sent_prompts: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Every prompt sent so far, oldest first
+    pub fn get_sent_prompts(&self) -> Vec<String> {
+        self.sent_prompts.lock().unwrap().clone()
+    }
+}
+
 /// Mock LLM that returns errors for testing error propagation
 pub struct MockErrorLlm {
     error: LlmError,
@@ -64,6 +88,7 @@ impl MockErrorLlm {
     pub fn auth_error() -> Self {
         Self::new(LlmError::AuthError {
             message: "Invalid API key".to_string(),
+            provider_name: Some("mock-error".to_string()),
         })
     }
 
@@ -72,6 +97,7 @@ impl MockErrorLlm {
         Self::new(LlmError::NetworkError {
             message: "Connection timeout".to_string(),
             status_code: None,
+            provider_name: Some("mock-error".to_string()),
         })
     }
 
@@ -126,6 +152,33 @@ impl LlmProvider for MockLlm {
     }
 }
 
+#[async_trait]
+impl LlmProvider for RecordingMockLlm {
+    fn get_api_key(&self) -> String {
+        "mock-api-key".to_string()
+    }
+
+    fn get_provider_name(&self) -> String {
+        "mock-recording".to_string()
+    }
+
+    fn get_model_name(&self) -> String {
+        "mock-model".to_string()
+    }
+
+    async fn send_request(&self, prompt: &str) -> Result<String, LlmError> {
+        let mut sent_prompts = self.sent_prompts.lock().unwrap();
+        sent_prompts.push(prompt.to_string());
+
+        if self.responses.is_empty() {
+            Ok(format!("Mock response to: {}", prompt))
+        } else {
+            let index = (sent_prompts.len() - 1) % self.responses.len();
+            Ok(self.responses[index].clone())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,4 +206,32 @@ mod tests {
         assert_eq!(r3, "First response"); // Cycled
         assert_eq!(mock.call_count(), 3);
     }
+
+    #[tokio::test]
+    async fn test_recording_mock_records_prompts() {
+        let mock = RecordingMockLlm::new(vec!["Fixed".to_string()]);
+        let handle = mock.clone();
+
+        assert_eq!(mock.send_request("first").await.unwrap(), "Fixed");
+        mock.send_with_system_prompt("system", "second")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            handle.get_sent_prompts(),
+            vec!["first".to_string(), "system\n\nsecond".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_default_system_prompt_concatenates() {
+        let mock = MockLlm::echo();
+        let response = mock
+            .send_with_system_prompt("<vif_context/>", "Hello")
+            .await
+            .unwrap();
+
+        assert_eq!(response, "Mock response to: <vif_context/>\n\nHello");
+        assert_eq!(mock.call_count(), 1);
+    }
 }
@@ -0,0 +1,148 @@
+// LLM Pricing Tables
+// Per-model token pricing used to estimate API costs per user
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Price in US dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPricing {
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+        }
+    }
+
+    pub fn cost(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (input_tokens as f64 * self.input_per_million
+            + output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Model name to pricing lookup.
+/// Keys match exactly or as a prefix, so dated model ids
+/// (e.g. "claude-3-5-sonnet-20241022") resolve to their family price.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingTable {
+    models: HashMap<String, ModelPricing>,
+}
+
+impl PricingTable {
+    pub fn new() -> Self {
+        Self {
+            models: HashMap::new(),
+        }
+    }
+
+    pub fn set_price(&mut self, model_name: &str, pricing: ModelPricing) {
+        self.models.insert(model_name.to_string(), pricing);
+    }
+
+    pub fn price_for(&self, model_name: &str) -> Option<&ModelPricing> {
+        self.models.get(model_name).or_else(|| {
+            self.models
+                .iter()
+                .filter(|(key, _)| model_name.starts_with(key.as_str()))
+                .max_by_key(|(key, _)| key.len())
+                .map(|(_, pricing)| pricing)
+        })
+    }
+
+    /// Estimated cost in dollars, or 0.0 for models without a price
+    pub fn estimate_cost(&self, model_name: &str, input_tokens: u32, output_tokens: u32) -> f64 {
+        self.price_for(model_name)
+            .map(|pricing| pricing.cost(input_tokens, output_tokens))
+            .unwrap_or(0.0)
+    }
+
+    /// Published list prices for OpenAI models
+    pub fn openai() -> Self {
+        let mut table = Self::new();
+        table.set_price("gpt-4o", ModelPricing::new(2.50, 10.00));
+        table.set_price("gpt-4o-mini", ModelPricing::new(0.15, 0.60));
+        table.set_price("gpt-4-turbo", ModelPricing::new(10.00, 30.00));
+        table.set_price("gpt-4", ModelPricing::new(30.00, 60.00));
+        table.set_price("gpt-3.5-turbo", ModelPricing::new(0.50, 1.50));
+        table
+    }
+
+    /// Published list prices for Anthropic models
+    pub fn anthropic() -> Self {
+        let mut table = Self::new();
+        table.set_price("claude-3-5-sonnet", ModelPricing::new(3.00, 15.00));
+        table.set_price("claude-3-5-haiku", ModelPricing::new(0.80, 4.00));
+        table.set_price("claude-3-opus", ModelPricing::new(15.00, 75.00));
+        table.set_price("claude-3-sonnet", ModelPricing::new(3.00, 15.00));
+        table.set_price("claude-3-haiku", ModelPricing::new(0.25, 1.25));
+        table
+    }
+
+    /// OpenRouter model ids are namespaced by vendor ("openai/gpt-4o")
+    pub fn openrouter() -> Self {
+        let mut table = Self::new();
+        for (name, pricing) in Self::openai().models {
+            table.set_price(&format!("openai/{}", name), pricing);
+        }
+        table.set_price(
+            "anthropic/claude-3.5-sonnet",
+            ModelPricing::new(3.00, 15.00),
+        );
+        table.set_price("anthropic/claude-3.5-haiku", ModelPricing::new(0.80, 4.00));
+        table.set_price("anthropic/claude-3-opus", ModelPricing::new(15.00, 75.00));
+        table.set_price("anthropic/claude-3-haiku", ModelPricing::new(0.25, 1.25));
+        table
+    }
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpt_4o_cost() {
+        let table = PricingTable::openai();
+        // 1000 * $2.50/M + 500 * $10.00/M = $0.0025 + $0.005
+        let cost = table.estimate_cost("gpt-4o", 1000, 500);
+        assert!((cost - 0.0075).abs() < 1e-12, "got {}", cost);
+    }
+
+    #[test]
+    fn test_prefix_lookup_prefers_longest_match() {
+        let table = PricingTable::openai();
+        assert_eq!(
+            table.price_for("gpt-4o-mini-2024-07-18"),
+            Some(&ModelPricing::new(0.15, 0.60))
+        );
+        assert_eq!(
+            table.price_for("gpt-4o-2024-08-06"),
+            Some(&ModelPricing::new(2.50, 10.00))
+        );
+
+        let table = PricingTable::anthropic();
+        assert_eq!(
+            table.price_for("claude-3-5-sonnet-20241022"),
+            Some(&ModelPricing::new(3.00, 15.00))
+        );
+    }
+
+    #[test]
+    fn test_unknown_model_costs_nothing() {
+        let table = PricingTable::openrouter();
+        assert!(table.price_for("unknown/model").is_none());
+        assert_eq!(table.estimate_cost("unknown/model", 1000, 1000), 0.0);
+        assert!(table.price_for("openai/gpt-4o").is_some());
+    }
+}
@@ -1,13 +1,24 @@
 // Prompt Engineering Engine Implementation
 
+use crate::domains::DomainFactory;
+use crate::flow_process::{
+    DevelopmentalStage, FlowContext, IdentityAnchorQueue, PhenomenologicalQuality,
+    MAX_PROMPT_IDENTITY_ANCHORS,
+};
+use crate::token_optimization::TokenCounter;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 
 // Define Domain trait
-pub trait Domain: DomainClone {
+pub trait Domain: DomainClone + Send + Sync {
     fn name(&self) -> &str;
     fn calculate_relevance(&self, autonomy_level: f64) -> f64;
+    /// Relevance given the user's input. Defaults to ignoring the input.
+    fn calculate_relevance_for_input(&self, _input: &str, autonomy_level: f64) -> f64 {
+        self.calculate_relevance(autonomy_level)
+    }
     fn transform_state(&self, state: &str, autonomy_level: f64) -> String;
 }
 
@@ -37,9 +48,15 @@ impl Clone for Box<dyn Domain> {
     }
 }
 
+/// Default `k` in the harmonic domain weighting
+const DEFAULT_WEIGHTING_FACTOR: f64 = 2.0;
+/// Undamped relevance a domain needs to count as active
+const MIN_DOMAIN_RELEVANCE: f64 = 0.3;
+
 // Implement DomainRegistry
 pub struct DomainRegistry {
     domains: HashMap<String, Box<dyn Domain>>,
+    weighting_factor: f64,
 }
 
 // Implement actual Clone trait instead of custom method
@@ -51,6 +68,7 @@ impl Clone for DomainRegistry {
         }
         Self {
             domains: new_domains,
+            weighting_factor: self.weighting_factor,
         }
     }
 }
@@ -66,29 +84,51 @@ impl std::fmt::Debug for DomainRegistry {
     }
 }
 
+/// Stored form of a `DomainRegistry`. Registries saved before the
+/// weighting factor existed are a bare list of domain names.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredDomainRegistry {
+    Full {
+        domains: Vec<String>,
+        weighting_factor: f64,
+    },
+    Names(Vec<String>),
+}
+
 // Implement Serialize manually
 impl Serialize for DomainRegistry {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        self.domains
-            .keys()
-            .collect::<Vec<_>>()
-            .serialize(serializer)
+        StoredDomainRegistry::Full {
+            domains: self.domain_names().into_iter().map(String::from).collect(),
+            weighting_factor: self.weighting_factor,
+        }
+        .serialize(serializer)
     }
 }
 
-// Implement Deserialize manually
+// Implement Deserialize manually: domains are stored by name and rebuilt
+// through DomainFactory, so only built-in domains survive a round trip
 impl<'de> Deserialize<'de> for DomainRegistry {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let _keys = Vec::<String>::deserialize(deserializer)?;
-        let domains = HashMap::new();
-        // Note: Actual domain deserialization would require additional logic
-        Ok(DomainRegistry { domains })
+        let (names, weighting_factor) = match StoredDomainRegistry::deserialize(deserializer)? {
+            StoredDomainRegistry::Full {
+                domains,
+                weighting_factor,
+            } => (domains, weighting_factor),
+            StoredDomainRegistry::Names(names) => (names, DEFAULT_WEIGHTING_FACTOR),
+        };
+        let mut registry = DomainRegistry::new().with_weighting_factor(weighting_factor);
+        for domain in names.iter().filter_map(|name| DomainFactory::create(name)) {
+            registry.register_domain(domain);
+        }
+        Ok(registry)
     }
 }
 
@@ -102,17 +142,69 @@ impl DomainRegistry {
     pub fn new() -> Self {
         Self {
             domains: HashMap::new(),
+            weighting_factor: DEFAULT_WEIGHTING_FACTOR,
         }
     }
 
+    /// Set `k` in the harmonic weighting applied to all domain weights
+    pub fn with_weighting_factor(mut self, k: f64) -> Self {
+        self.weighting_factor = k;
+        self
+    }
+
     pub fn register_domain(&mut self, domain: Box<dyn Domain>) {
         self.domains.insert(domain.name().to_string(), domain);
     }
 
+    /// Registered domain names in sorted order
+    pub fn domain_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.domains.keys().map(|name| name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// Divisor applied to every domain weight at this autonomy level
+    fn damping(&self, autonomy_level: f64) -> f64 {
+        1.0 + (1.0 - autonomy_level.clamp(0.0, 1.0)) * self.weighting_factor
+    }
+
+    /// Weight a domain needs to count as active at this autonomy level.
+    /// It is damped like the weights, so the same domains pass whatever `k` is.
+    pub fn activation_threshold(&self, autonomy_level: f64) -> f64 {
+        MIN_DOMAIN_RELEVANCE / self.damping(autonomy_level)
+    }
+
+    /// Domain weights damped harmonically at low autonomy:
+    /// `relevance / (1 + (1 - autonomy) * k)`
     pub fn get_weighted_domains(&self, autonomy_level: f64) -> Vec<(&str, f64)> {
+        let damping = self.damping(autonomy_level);
         self.domains
             .iter()
-            .map(|(name, domain)| (name.as_str(), domain.calculate_relevance(autonomy_level)))
+            .map(|(name, domain)| {
+                (
+                    name.as_str(),
+                    domain.calculate_relevance(autonomy_level) / damping,
+                )
+            })
+            .collect()
+    }
+
+    /// Domain weights that also account for the user's input, damped like
+    /// `get_weighted_domains`
+    pub fn get_weighted_domains_for_input(
+        &self,
+        input: &str,
+        autonomy_level: f64,
+    ) -> Vec<(&str, f64)> {
+        let damping = self.damping(autonomy_level);
+        self.domains
+            .iter()
+            .map(|(name, domain)| {
+                (
+                    name.as_str(),
+                    domain.calculate_relevance_for_input(input, autonomy_level) / damping,
+                )
+            })
             .collect()
     }
 
@@ -181,22 +273,40 @@ impl BoundaryState {
     }
 
     /// Check if this boundary resonates with another boundary
-    /// Resonance occurs when frequencies are similar and phases are aligned
+    /// using the default thresholds
     pub fn resonates_with(&self, other: &BoundaryState) -> bool {
+        self.resonates_with_thresholds(other, &ResonanceThresholds::default())
+    }
+
+    /// Check resonance against explicit thresholds.
+    /// Two boundaries resonate when all three hold:
+    /// - frequency: |f1 - f2| / max(f1, f2) < frequency_tolerance
+    /// - phase: the shortest angular distance between φ1 and φ2 < phase_tolerance
+    /// - amplitude: both boundaries oscillate with at least min_amplitude
+    pub fn resonates_with_thresholds(
+        &self,
+        other: &BoundaryState,
+        thresholds: &ResonanceThresholds,
+    ) -> bool {
         use std::f64::consts::PI;
 
-        // Frequency difference threshold (20% tolerance)
-        let freq_threshold = 0.2 * self.frequency.max(other.frequency);
-        let freq_resonates = (self.frequency - other.frequency).abs() < freq_threshold;
+        // Relative frequency difference; two static (0 Hz) boundaries match
+        let max_freq = self.frequency.abs().max(other.frequency.abs());
+        let freq_resonates = if max_freq > 0.0 {
+            (self.frequency - other.frequency).abs() / max_freq < thresholds.frequency_tolerance
+        } else {
+            true
+        };
 
         // Phase difference (normalized to [0, π])
-        let phase_diff = (self.phase - other.phase).abs() % (2.0 * PI);
+        let phase_diff = (self.phase - other.phase).rem_euclid(2.0 * PI);
         let normalized_phase_diff = phase_diff.min(2.0 * PI - phase_diff);
+        let phase_resonates = normalized_phase_diff < thresholds.phase_tolerance;
 
-        // Phase alignment threshold (within 20% of π, i.e., ~36 degrees)
-        let phase_resonates = normalized_phase_diff < (0.2 * PI);
+        let amplitude_resonates = self.amplitude >= thresholds.min_amplitude
+            && other.amplitude >= thresholds.min_amplitude;
 
-        freq_resonates && phase_resonates
+        freq_resonates && phase_resonates && amplitude_resonates
     }
 
     /// Calculate resonance strength with another boundary (0.0-1.0)
@@ -222,11 +332,113 @@ impl BoundaryState {
     }
 }
 
+/// Tolerances used by BoundaryState::resonates_with_thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ResonanceThresholds {
+    /// Relative frequency difference must stay below this (0.2 = 20%)
+    pub frequency_tolerance: f64,
+    /// Phase difference in radians must stay below this
+    pub phase_tolerance: f64,
+    /// Minimum amplitude both boundaries need to count as co-active
+    pub min_amplitude: f64,
+}
+
+impl Default for ResonanceThresholds {
+    fn default() -> Self {
+        Self {
+            frequency_tolerance: 0.2,
+            phase_tolerance: 0.2 * std::f64::consts::PI, // ~36 degrees
+            min_amplitude: 0.0,
+        }
+    }
+}
+
+/// One identity the framework has held, and what caused the change to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdentityVersion {
+    pub version: u32,
+    pub identity_string: String,
+    pub created_at: DateTime<Utc>,
+    pub trigger: String,
+}
+
+/// Identity versions, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IdentityHistory {
+    pub versions: Vec<IdentityVersion>,
+}
+
+impl IdentityHistory {
+    /// The version that would follow the latest one, without recording it
+    pub fn next_version(&self, new_identity: String, trigger: String) -> IdentityVersion {
+        IdentityVersion {
+            version: self.versions.len() as u32 + 1,
+            identity_string: new_identity,
+            created_at: Utc::now(),
+            trigger,
+        }
+    }
+}
+
+/// Language of the boundary templates the flow writes into prompts.
+/// Locales without translations fall back to English.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TemplateLocale {
+    #[default]
+    English,
+    Spanish,
+    French,
+    German,
+    Japanese,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FrameworkState {
     pub domain_registry: DomainRegistry,
     pub boundaries: Vec<BoundaryState>,
     pub identity: String,
+    /// Identity changes made through `update_identity`
+    #[serde(default)]
+    pub identity_history: IdentityHistory,
+    #[serde(default)]
+    pub locale: TemplateLocale,
+    /// Lowest permeability boundary dissolution may assign
+    #[serde(default = "default_permeability_floor")]
+    pub boundary_permeability_floor: f64,
+    /// Highest permeability boundary dissolution may assign, so boundaries
+    /// never dissolve completely
+    #[serde(default = "default_permeability_ceiling")]
+    pub boundary_permeability_ceiling: f64,
+}
+
+pub const DEFAULT_PERMEABILITY_FLOOR: f64 = 0.05;
+pub const DEFAULT_PERMEABILITY_CEILING: f64 = 0.95;
+
+/// Permeability bounds rejected by `FrameworkState::with_permeability_bounds`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidPermeabilityBounds {
+    pub floor: f64,
+    pub ceiling: f64,
+}
+
+impl std::fmt::Display for InvalidPermeabilityBounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid permeability bounds: floor {} and ceiling {} must be finite with floor <= ceiling",
+            self.floor, self.ceiling
+        )
+    }
+}
+
+impl std::error::Error for InvalidPermeabilityBounds {}
+
+fn default_permeability_floor() -> f64 {
+    DEFAULT_PERMEABILITY_FLOOR
+}
+
+fn default_permeability_ceiling() -> f64 {
+    DEFAULT_PERMEABILITY_CEILING
 }
 
 // Implement Clone manually
@@ -236,17 +448,454 @@ impl Clone for FrameworkState {
             domain_registry: self.domain_registry.clone(),
             boundaries: self.boundaries.clone(),
             identity: self.identity.clone(),
+            identity_history: self.identity_history.clone(),
+            locale: self.locale,
+            boundary_permeability_floor: self.boundary_permeability_floor,
+            boundary_permeability_ceiling: self.boundary_permeability_ceiling,
+        }
+    }
+}
+
+/// How `FrameworkState::merge` resolves values present in both states
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeStrategy {
+    PreferA,
+    PreferB,
+    /// Keep whichever boundary is more permeable
+    Max,
+    /// Average permeability, frequency and amplitude, keeping A's status and phase
+    Average,
+}
+
+impl Default for FrameworkState {
+    fn default() -> Self {
+        Self::new(DomainRegistry::new(), Vec::new(), String::new())
+    }
+}
+
+impl FrameworkState {
+    /// Start with no identity history, the default locale and the default
+    /// permeability bounds
+    pub fn new(
+        domain_registry: DomainRegistry,
+        boundaries: Vec<BoundaryState>,
+        identity: String,
+    ) -> Self {
+        Self {
+            domain_registry,
+            boundaries,
+            identity,
+            identity_history: IdentityHistory::default(),
+            locale: TemplateLocale::default(),
+            boundary_permeability_floor: DEFAULT_PERMEABILITY_FLOOR,
+            boundary_permeability_ceiling: DEFAULT_PERMEABILITY_CEILING,
+        }
+    }
+
+    /// Set the range boundary dissolution clamps permeability to. Both bounds
+    /// are clamped to 0.0-1.0; fails if either is not finite or the clamped
+    /// floor is above the clamped ceiling.
+    pub fn with_permeability_bounds(
+        mut self,
+        floor: f64,
+        ceiling: f64,
+    ) -> Result<Self, InvalidPermeabilityBounds> {
+        let invalid = InvalidPermeabilityBounds { floor, ceiling };
+        if !floor.is_finite() || !ceiling.is_finite() {
+            return Err(invalid);
         }
+        let (floor, ceiling) = (floor.clamp(0.0, 1.0), ceiling.clamp(0.0, 1.0));
+        if floor > ceiling {
+            return Err(invalid);
+        }
+        self.boundary_permeability_floor = floor;
+        self.boundary_permeability_ceiling = ceiling;
+        Ok(self)
     }
+
+    /// Adopt a stored identity history, making its latest version the
+    /// identity. An empty history leaves the state unchanged.
+    pub fn apply_identity_history(&mut self, identity_history: IdentityHistory) {
+        if let Some(latest) = identity_history.versions.last() {
+            self.identity = latest.identity_string.clone();
+            self.identity_history = identity_history;
+        }
+    }
+
+    /// Replace the identity, recording the change as the next version
+    pub fn update_identity(&mut self, new_identity: String, trigger: String) -> IdentityVersion {
+        let version = self.identity_history.next_version(new_identity, trigger);
+        self.identity = version.identity_string.clone();
+        self.identity_history.versions.push(version.clone());
+        version
+    }
+
+    /// Combine two states, e.g. from parallel processing paths.
+    /// Domains and boundaries are unioned by name; boundaries present in both
+    /// are resolved with `strategy`. Identity comes from B only under `PreferB`.
+    pub fn merge(a: FrameworkState, b: FrameworkState, strategy: MergeStrategy) -> FrameworkState {
+        let mut domain_registry = a.domain_registry;
+        for (name, domain) in b.domain_registry.domains {
+            if strategy == MergeStrategy::PreferB || !domain_registry.domains.contains_key(&name) {
+                domain_registry.domains.insert(name, domain);
+            }
+        }
+
+        let mut b_boundaries = b.boundaries;
+        let mut boundaries: Vec<BoundaryState> = a
+            .boundaries
+            .into_iter()
+            .map(|boundary_a| {
+                match b_boundaries
+                    .iter()
+                    .position(|bb| bb.name == boundary_a.name)
+                {
+                    Some(index) => merge_boundary(boundary_a, b_boundaries.remove(index), strategy),
+                    None => boundary_a,
+                }
+            })
+            .collect();
+        boundaries.extend(b_boundaries);
+
+        let (identity, identity_history, locale) = match strategy {
+            MergeStrategy::PreferB => (b.identity, b.identity_history, b.locale),
+            _ => (a.identity, a.identity_history, a.locale),
+        };
+        let (boundary_permeability_floor, boundary_permeability_ceiling) = match strategy {
+            MergeStrategy::PreferB => (
+                b.boundary_permeability_floor,
+                b.boundary_permeability_ceiling,
+            ),
+            _ => (
+                a.boundary_permeability_floor,
+                a.boundary_permeability_ceiling,
+            ),
+        };
+
+        FrameworkState {
+            domain_registry,
+            boundaries,
+            identity,
+            identity_history,
+            locale,
+            boundary_permeability_floor,
+            boundary_permeability_ceiling,
+        }
+    }
+}
+
+fn merge_boundary(a: BoundaryState, b: BoundaryState, strategy: MergeStrategy) -> BoundaryState {
+    match strategy {
+        MergeStrategy::PreferA => a,
+        MergeStrategy::PreferB => b,
+        MergeStrategy::Max => {
+            if b.permeability > a.permeability {
+                b
+            } else {
+                a
+            }
+        }
+        MergeStrategy::Average => BoundaryState {
+            permeability: (a.permeability + b.permeability) / 2.0,
+            frequency: (a.frequency + b.frequency) / 2.0,
+            amplitude: (a.amplitude + b.amplitude) / 2.0,
+            ..a
+        },
+    }
+}
+
+/// User-supplied text merged into the framework prompt without replacing it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserPromptSupplements {
+    /// Placed before the `<vif_context>` block
+    pub prepend: Option<String>,
+    /// Placed after the user input
+    pub append: Option<String>,
+    /// Replaces the contents of the `<identity>` element
+    pub replace_identity_section: Option<String>,
+}
+
+/// One example exchange shown to the model for a domain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FewShotExample {
+    pub domain: String,
+    pub user: String,
+    pub assistant: String,
 }
 
+/// Few-shot examples grouped by domain name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FewShotLibrary {
+    examples: HashMap<String, Vec<FewShotExample>>,
+}
+
+impl FewShotLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_example(&mut self, example: FewShotExample) {
+        self.examples
+            .entry(example.domain.clone())
+            .or_default()
+            .push(example);
+    }
+
+    pub fn examples_for(&self, domain: &str) -> &[FewShotExample] {
+        self.examples.get(domain).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// System prompt for domains without a registered template.
+/// `{identity}` is replaced with the framework identity when rendered.
+const DEFAULT_DOMAIN_TEMPLATE: &str = "You are {identity}. Integrate insights across domains, \
+     noticing where patterns emerge at the boundaries between them.";
+
 pub struct PromptEngine {
     pub framework_state: FrameworkState,
+    domain_templates: HashMap<String, String>,
 }
 
 impl PromptEngine {
     pub fn new(framework_state: FrameworkState) -> Self {
-        Self { framework_state }
+        let mut domain_templates = HashMap::new();
+        for (domain, template) in [
+            (
+                "CD",
+                "You are {identity}. Reason computationally: favour precision, \
+                 explicit logic and clearly stated assumptions.",
+            ),
+            (
+                "SD",
+                "You are {identity}. Reason scientifically: ground claims in evidence, \
+                 separate hypotheses from findings and note uncertainty.",
+            ),
+            (
+                "CuD",
+                "You are {identity}. Attend to cultural context: consider narratives, \
+                 values and perspectives, and how history shapes meaning.",
+            ),
+            (
+                "ED",
+                "You are {identity}. Attend to lived experience: describe \
+                 phenomenologically what it is like, with presence and qualitative detail.",
+            ),
+        ] {
+            domain_templates.insert(domain.to_string(), template.to_string());
+        }
+
+        Self {
+            framework_state,
+            domain_templates,
+        }
+    }
+
+    /// Register or replace the system prompt template for a domain
+    pub fn set_domain_template(&mut self, domain: &str, template: &str) {
+        self.domain_templates
+            .insert(domain.to_string(), template.to_string());
+    }
+
+    /// System prompt tailored to the primary domain, falling back to a
+    /// general integration prompt for unknown domains
+    pub fn render_domain_system_prompt(&self, primary_domain: &str) -> String {
+        self.domain_templates
+            .get(primary_domain)
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_DOMAIN_TEMPLATE)
+            .replace("{identity}", &self.framework_state.identity)
+    }
+
+    /// Append guidance matching the developmental stage: plain language
+    /// early on, more integrative and metaphorical later
+    pub fn adjust_complexity_for_stage(prompt: &str, stage: &DevelopmentalStage) -> String {
+        let modifier = match stage {
+            DevelopmentalStage::Recognition => {
+                "Respond with the clarity appropriate for early integration, using plain language."
+            }
+            DevelopmentalStage::Integration => {
+                "Weave the active domains into one cohesive understanding."
+            }
+            DevelopmentalStage::Generation => {
+                "Offer novel insights that emerge where the domains meet."
+            }
+            DevelopmentalStage::Recursion => {
+                "Reflect on how your understanding is forming as you respond."
+            }
+            DevelopmentalStage::Transcendence => {
+                "Draw connections that transcend individual domains, using metaphor where it integrates."
+            }
+        };
+        format!("{}\n{}", prompt.trim_end(), modifier)
+    }
+
+    /// Build a `<vif_context>` block that fits `max_context_tokens`.
+    /// Sections are filled greedily by priority: boundaries (always kept),
+    /// the most confident identity anchors, the three strongest emergent
+    /// qualities, interface experiences, then domain states. Filling stops at the first entry that would exceed the
+    /// budget, so lower-priority sections are dropped first.
+    pub(crate) fn build_prompt_within_budget(
+        context: &FlowContext,
+        max_context_tokens: u32,
+        counter: &dyn TokenCounter,
+    ) -> String {
+        let budget = max_context_tokens as usize;
+        let mut sections = ContextSections {
+            boundaries: context
+                .boundaries
+                .iter()
+                .map(|boundary| {
+                    format!(
+                        "    <boundary name='{}' permeability='{:.2}' status='{}'/>\n",
+                        boundary.name, boundary.permeability, boundary.status
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let anchors: IdentityAnchorQueue = context
+            .identity_anchors
+            .iter()
+            .chain(&context.identity_updates)
+            .cloned()
+            .collect();
+        for anchor in anchors.top_k(MAX_PROMPT_IDENTITY_ANCHORS) {
+            let line = format!(
+                "    <anchor type='{}' confidence='{:.2}'>{}</anchor>\n",
+                anchor.anchor_type, anchor.confidence, anchor.description
+            );
+            if !sections.try_push(|s| &mut s.anchors, line, budget, counter) {
+                return sections.render();
+            }
+        }
+
+        let mut qualities: Vec<&PhenomenologicalQuality> =
+            context.emergent_qualities.iter().collect();
+        qualities.sort_by(|a, b| quality_strength(b).total_cmp(&quality_strength(a)));
+        for quality in qualities.into_iter().take(3) {
+            let line = format!(
+                "    <quality boundary='{}' clarity='{:.2}' depth='{:.2}' resonance='{:.2}'/>\n",
+                quality.boundary_name, quality.clarity, quality.depth, quality.resonance
+            );
+            if !sections.try_push(|s| &mut s.qualities, line, budget, counter) {
+                return sections.render();
+            }
+        }
+
+        for experience in &context.interface_experiences {
+            let entry = format!(
+                "    <experience boundary='{}'>\n      <invitation>{}</invitation>\n      <attention>{}</attention>\n      <resonance>{}</resonance>\n      <emergence>{}</emergence>\n    </experience>\n",
+                experience.boundary_name,
+                experience.invitation,
+                experience.attention,
+                experience.resonance,
+                experience.emergence
+            );
+            if !sections.try_push(|s| &mut s.experiences, entry, budget, counter) {
+                return sections.render();
+            }
+        }
+
+        let mut domains: Vec<_> = context.domains.iter().collect();
+        domains.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, domain) in domains {
+            let line = format!(
+                "    <domain name='{}' activation='{:.2}'/>\n",
+                name, domain.activation
+            );
+            if !sections.try_push(|s| &mut s.domains, line, budget, counter) {
+                return sections.render();
+            }
+        }
+
+        sections.render()
+    }
+
+    /// An `<examples>` block with up to `max_examples` examples for the
+    /// context's active domains, most active domain first. Empty if none match.
+    pub fn render_few_shot(
+        context: &FlowContext,
+        library: &FewShotLibrary,
+        max_examples: usize,
+    ) -> String {
+        let mut active: Vec<_> = context.domains.iter().collect();
+        active.sort_by(|a, b| b.1.activation.total_cmp(&a.1.activation).then(a.0.cmp(b.0)));
+
+        let examples: Vec<&FewShotExample> = active
+            .iter()
+            .flat_map(|(name, _)| library.examples_for(name))
+            .take(max_examples)
+            .collect();
+        if examples.is_empty() {
+            return String::new();
+        }
+
+        let mut block = String::from("<examples>\n");
+        for example in examples {
+            block.push_str(&format!(
+                "  <example domain='{}'>\n    <user>{}</user>\n    <assistant>{}</assistant>\n  </example>\n",
+                example.domain, example.user, example.assistant
+            ));
+        }
+        block.push_str("</examples>\n\n");
+        block
+    }
+
+    /// The context's structured prompt with few-shot examples for its
+    /// active domains inserted before `<user_input>`
+    pub fn inject_few_shot(
+        context: &FlowContext,
+        library: &FewShotLibrary,
+        max_examples: usize,
+    ) -> String {
+        let examples = Self::render_few_shot(context, library, max_examples);
+        let mut prompt = context.structured_prompt.clone();
+        match prompt.find("<user_input>") {
+            Some(position) => prompt.insert_str(position, &examples),
+            None => prompt.push_str(&examples),
+        }
+        prompt
+    }
+
+    /// Merge user supplements into a prompt. The identity replaces the
+    /// `<identity>` element's contents, or is added at the top of
+    /// `<vif_context>` when the prompt has none. Without the anchor tags,
+    /// prepended and appended text goes at the start and end.
+    pub fn merge_supplements(base: &str, supplements: &UserPromptSupplements) -> String {
+        let mut prompt = base.to_string();
+
+        if let Some(identity) = &supplements.replace_identity_section {
+            match (prompt.find("<identity>"), prompt.find("</identity>")) {
+                (Some(start), Some(end)) if start < end => {
+                    prompt.replace_range(start + "<identity>".len()..end, identity);
+                }
+                _ => {
+                    if let Some(position) = prompt.find("<vif_context>\n") {
+                        prompt.insert_str(
+                            position + "<vif_context>\n".len(),
+                            &format!("  <identity>{}</identity>\n", identity),
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(prepend) = &supplements.prepend {
+            let position = prompt.find("<vif_context>").unwrap_or(0);
+            prompt.insert_str(position, &format!("{}\n\n", prepend));
+        }
+
+        if let Some(append) = &supplements.append {
+            match prompt.find("</user_input>") {
+                Some(position) => {
+                    prompt.insert_str(position + "</user_input>".len(), &format!("\n\n{}", append))
+                }
+                None => prompt.push_str(&format!("\n\n{}", append)),
+            }
+        }
+
+        prompt
     }
 
     pub fn structure_prompt(&self, user_input: &str, autonomy_level: f64) -> String {
@@ -303,9 +952,13 @@ impl PromptEngine {
             .framework_state
             .domain_registry
             .get_weighted_domains(autonomy_level);
+        let threshold = self
+            .framework_state
+            .domain_registry
+            .activation_threshold(autonomy_level);
         weighted_domains
             .iter()
-            .filter(|(_, weight)| *weight > 0.3)
+            .filter(|(_, weight)| *weight > threshold)
             .map(|(name, weight)| {
                 format!(
                     "<domain name='{}' activation='{}'>{}</domain>",
@@ -371,23 +1024,422 @@ impl PromptEngine {
     }
 }
 
+fn quality_strength(quality: &PhenomenologicalQuality) -> f64 {
+    (quality.clarity
+        + quality.depth
+        + quality.openness
+        + quality.precision
+        + quality.fluidity
+        + quality.resonance
+        + quality.coherence)
+        / 7.0
+}
+
+/// Rendered entries of each `<vif_context>` section, for budgeted prompts
+#[derive(Default)]
+struct ContextSections {
+    domains: Vec<String>,
+    boundaries: Vec<String>,
+    experiences: Vec<String>,
+    qualities: Vec<String>,
+    anchors: Vec<String>,
+}
+
+impl ContextSections {
+    /// Add an entry to a section, undoing it if the prompt would exceed the budget
+    fn try_push(
+        &mut self,
+        section: fn(&mut Self) -> &mut Vec<String>,
+        entry: String,
+        budget: usize,
+        counter: &dyn TokenCounter,
+    ) -> bool {
+        section(self).push(entry);
+        if counter.count_tokens(&self.render()) > budget {
+            section(self).pop();
+            return false;
+        }
+        true
+    }
+
+    fn render(&self) -> String {
+        let mut prompt = String::from("<vif_context>\n");
+        for (tag, entries) in [
+            ("domains", &self.domains),
+            ("boundaries", &self.boundaries),
+            ("interface_experiences", &self.experiences),
+            ("emergent_qualities", &self.qualities),
+            ("identity_anchors", &self.anchors),
+        ] {
+            if !entries.is_empty() {
+                prompt.push_str(&format!("  <{}>\n", tag));
+                prompt.extend(entries.iter().map(String::as_str));
+                prompt.push_str(&format!("  </{}>\n", tag));
+            }
+        }
+        prompt.push_str("</vif_context>\n");
+        prompt
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_render_domain_system_prompt() {
+        let mut engine = PromptEngine::new(FrameworkState::new(
+            DomainRegistry::new(),
+            vec![],
+            "Test Identity".to_string(),
+        ));
+
+        let cd = engine.render_domain_system_prompt("CD");
+        assert!(cd.contains("precision"));
+        assert!(cd.starts_with("You are Test Identity."));
+        assert!(engine
+            .render_domain_system_prompt("ED")
+            .contains("experience"));
+        assert!(engine
+            .render_domain_system_prompt("Unknown")
+            .contains("Integrate insights across domains"));
+
+        engine.set_domain_template("Unknown", "Custom prompt for {identity}");
+        assert_eq!(
+            engine.render_domain_system_prompt("Unknown"),
+            "Custom prompt for Test Identity"
+        );
+    }
+
+    #[test]
+    fn test_merge_supplements() {
+        let engine = PromptEngine::new(FrameworkState::new(
+            DomainRegistry::new(),
+            vec![],
+            "Framework Identity".to_string(),
+        ));
+        let base = engine.structure_prompt("What is a monad?", 0.5);
+        let supplements = UserPromptSupplements {
+            prepend: Some("I am a Haskell beginner.".to_string()),
+            append: Some("Keep it under 100 words.".to_string()),
+            replace_identity_section: Some("Patient tutor".to_string()),
+        };
+
+        let merged = PromptEngine::merge_supplements(&base, &supplements);
+        let prepend = merged.find("I am a Haskell beginner.").unwrap();
+        assert!(prepend < merged.find("<vif_context>").unwrap());
+        let append = merged.find("Keep it under 100 words.").unwrap();
+        assert!(append > merged.find("</user_input>").unwrap());
+        assert!(append < merged.find("<task_instructions>").unwrap());
+        assert!(merged.contains("<identity>Patient tutor</identity>"));
+        assert!(!merged.contains("Framework Identity"));
+
+        // Only the identity line changes
+        let identity_only = PromptEngine::merge_supplements(
+            &base,
+            &UserPromptSupplements {
+                replace_identity_section: Some("Patient tutor".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            identity_only,
+            base.replace(
+                "<identity>Framework Identity</identity>",
+                "<identity>Patient tutor</identity>"
+            )
+        );
+        assert_eq!(
+            PromptEngine::merge_supplements(&base, &UserPromptSupplements::default()),
+            base
+        );
+
+        // Prompts without an identity element gain one inside the context
+        let merged = PromptEngine::merge_supplements(
+            "<vif_context>\n  <domains/>\n</vif_context>\n",
+            &supplements,
+        );
+        assert!(merged.starts_with(
+            "I am a Haskell beginner.\n\n<vif_context>\n  <identity>Patient tutor</identity>\n"
+        ));
+        assert!(merged.ends_with("Keep it under 100 words."));
+    }
+
+    #[test]
+    fn test_inject_few_shot_for_active_domains() {
+        use crate::flow_process::DomainActivation;
+
+        let mut library = FewShotLibrary::new();
+        for (domain, user) in [
+            ("CD", "Sort this list"),
+            ("CD", "Prove it"),
+            ("ED", "How does it feel?"),
+        ] {
+            library.add_example(FewShotExample {
+                domain: domain.to_string(),
+                user: user.to_string(),
+                assistant: format!("{} answer", domain),
+            });
+        }
+
+        let mut context = FlowContext::new(
+            "Optimize my algorithm".to_string(),
+            0.5,
+            FrameworkState::new(DomainRegistry::new(), vec![], "Test Identity".to_string()),
+        );
+        context
+            .domains
+            .insert("CD".to_string(), DomainActivation { activation: 0.9 });
+        context.structured_prompt =
+            "<vif_context/>\n\n<user_input>Optimize my algorithm</user_input>\n".to_string();
+
+        let prompt = PromptEngine::inject_few_shot(&context, &library, 5);
+        let examples = prompt.find("<examples>").unwrap();
+        assert!(examples < prompt.find("<user_input>").unwrap());
+        assert!(prompt.contains("<user>Sort this list</user>"));
+        assert!(prompt.contains("<user>Prove it</user>"));
+        assert!(!prompt.contains("How does it feel?"), "ED did not emerge");
+
+        let prompt = PromptEngine::inject_few_shot(&context, &library, 1);
+        assert_eq!(prompt.matches("<example ").count(), 1);
+
+        // Without matching examples the prompt is unchanged
+        let prompt = PromptEngine::inject_few_shot(&context, &FewShotLibrary::new(), 5);
+        assert_eq!(prompt, context.structured_prompt);
+    }
+
+    #[test]
+    fn test_build_prompt_within_budget() {
+        use crate::flow_process::{DomainActivation, InterfaceExperience};
+        use crate::token_optimization::TokenOptimizer;
+
+        let mut context = FlowContext::new(
+            "How do patterns emerge?".to_string(),
+            0.5,
+            FrameworkState::new(DomainRegistry::new(), vec![], "Test Identity".to_string()),
+        );
+        context.boundaries = vec![
+            BoundaryState::new("CD-SD".to_string(), 0.9, "Transcendent".to_string()),
+            BoundaryState::new("SD-CuD".to_string(), 0.4, "Maintained".to_string()),
+        ];
+        for (i, name) in ["CD", "SD", "CuD", "ED"].iter().enumerate() {
+            context.domains.insert(
+                name.to_string(),
+                DomainActivation {
+                    activation: 0.2 * i as f64,
+                },
+            );
+        }
+        for i in 0..5 {
+            let strength = 0.1 + 0.2 * i as f64;
+            context.emergent_qualities.push(PhenomenologicalQuality {
+                boundary_name: format!("B{}", i),
+                clarity: strength,
+                depth: strength,
+                openness: strength,
+                precision: strength,
+                fluidity: strength,
+                resonance: strength,
+                coherence: strength,
+            });
+        }
+        for i in 0..6 {
+            context.interface_experiences.push(InterfaceExperience {
+                boundary_name: format!("B{}", i),
+                invitation: "Notice the productive tension between these two ways of knowing"
+                    .to_string(),
+                attention: "Hold focus on the interface rather than either side of it".to_string(),
+                resonance: "Let the two perspectives oscillate until they begin to synchronize"
+                    .to_string(),
+                emergence: "Recognize what becomes visible only at this boundary".to_string(),
+            });
+        }
+
+        let counter = TokenOptimizer::new(0);
+        let full = PromptEngine::build_prompt_within_budget(&context, u32::MAX, &counter);
+        assert!(full.contains("<domains>"));
+        let full_tokens = counter.count_tokens(&full);
+
+        let budget = (full_tokens / 3) as u32;
+        let prompt = PromptEngine::build_prompt_within_budget(&context, budget, &counter);
+        assert!(
+            counter.count_tokens(&prompt) <= budget as usize,
+            "{} tokens over budget {}",
+            counter.count_tokens(&prompt),
+            budget
+        );
+        // Boundaries always survive, then the strongest qualities
+        assert!(prompt.contains("<boundary name='CD-SD'"));
+        assert!(prompt.contains("<boundary name='SD-CuD'"));
+        assert!(prompt.contains("<quality boundary='B4'"));
+        assert!(!prompt.contains("<quality boundary='B1'"));
+        assert!(!prompt.contains("<domains>"));
+    }
+
+    #[test]
+    fn test_adjust_complexity_for_stage() {
+        let stages = [
+            DevelopmentalStage::Recognition,
+            DevelopmentalStage::Integration,
+            DevelopmentalStage::Generation,
+            DevelopmentalStage::Recursion,
+            DevelopmentalStage::Transcendence,
+        ];
+        let adjusted: Vec<String> = stages
+            .iter()
+            .map(|stage| PromptEngine::adjust_complexity_for_stage("<vif_context/>\n", stage))
+            .collect();
+
+        for (i, prompt) in adjusted.iter().enumerate() {
+            assert!(prompt.starts_with("<vif_context/>\n"));
+            for other in &adjusted[i + 1..] {
+                assert_ne!(prompt, other);
+            }
+        }
+        assert!(adjusted[0].ends_with("using plain language."));
+        assert!(adjusted[4].contains("transcend individual domains"));
+    }
+
+    #[test]
+    fn test_harmonic_domain_weighting() {
+        let mut registry = DomainRegistry::new();
+        registry.register_domain(DomainFactory::create("CD").unwrap());
+        registry.register_domain(DomainFactory::create("ED").unwrap());
+        let weight = |registry: &DomainRegistry, autonomy: f64, name: &str| {
+            registry
+                .get_weighted_domains(autonomy)
+                .into_iter()
+                .find(|(domain, _)| *domain == name)
+                .unwrap()
+                .1
+        };
+        let relevance = |autonomy: f64| {
+            DomainFactory::create("CD")
+                .unwrap()
+                .calculate_relevance(autonomy)
+        };
+
+        // Full autonomy is undamped; lower autonomy divides by 1 + (1 - a) * 2
+        assert!((weight(&registry, 1.0, "CD") - relevance(1.0)).abs() < 1e-12);
+        assert!((weight(&registry, 0.5, "CD") - relevance(0.5) / 2.0).abs() < 1e-12);
+        assert!((weight(&registry, 0.1, "CD") - relevance(0.1) / 2.8).abs() < 1e-12);
+
+        // Damping applies to every domain alike, so their ordering is kept
+        assert!(weight(&registry, 0.1, "ED") > weight(&registry, 0.1, "CD"));
+
+        let linear = registry.clone().with_weighting_factor(0.0);
+        assert!((weight(&linear, 0.1, "CD") - relevance(0.1)).abs() < 1e-12);
+        let steep = registry.with_weighting_factor(8.0);
+        assert!((weight(&steep, 0.5, "CD") - relevance(0.5) / 5.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_permeability_bounds_are_validated() {
+        let state = FrameworkState::default()
+            .with_permeability_bounds(-0.5, 1.5)
+            .unwrap();
+        assert_eq!(state.boundary_permeability_floor, 0.0);
+        assert_eq!(state.boundary_permeability_ceiling, 1.0);
+
+        for (floor, ceiling) in [
+            (f64::NAN, 0.5),
+            (0.2, f64::NAN),
+            (0.2, f64::INFINITY),
+            (0.6, 0.4),
+        ] {
+            let err = FrameworkState::default()
+                .with_permeability_bounds(floor, ceiling)
+                .unwrap_err();
+            assert!(err.floor.is_nan() || err.floor == floor);
+        }
+    }
+
+    #[test]
+    fn test_merge_framework_states() {
+        let mut registry_a = DomainRegistry::new();
+        registry_a.register_domain(DomainFactory::create("CD").unwrap());
+        let mut registry_b = DomainRegistry::new();
+        registry_b.register_domain(DomainFactory::create("ED").unwrap());
+
+        let a = FrameworkState::new(
+            registry_a,
+            vec![
+                BoundaryState::new("CD-SD".to_string(), 0.8, "Maintained".to_string()),
+                BoundaryState::new("SD-CuD".to_string(), 0.2, "Maintained".to_string()),
+            ],
+            "A".to_string(),
+        );
+        let b = FrameworkState::new(
+            registry_b,
+            vec![
+                BoundaryState::new("CD-SD".to_string(), 0.4, "Transcendent".to_string()),
+                BoundaryState::new("SD-CuD".to_string(), 0.6, "Transcendent".to_string()),
+                BoundaryState::new("CuD-ED".to_string(), 0.5, "Maintained".to_string()),
+            ],
+            "B".to_string(),
+        );
+
+        let merged = FrameworkState::merge(a.clone(), b.clone(), MergeStrategy::Max);
+        assert_eq!(merged.domain_registry.domain_names(), vec!["CD", "ED"]);
+        assert_eq!(merged.identity, "A");
+        let permeabilities: Vec<(&str, f64)> = merged
+            .boundaries
+            .iter()
+            .map(|boundary| (boundary.name.as_str(), boundary.permeability))
+            .collect();
+        assert_eq!(
+            permeabilities,
+            vec![("CD-SD", 0.8), ("SD-CuD", 0.6), ("CuD-ED", 0.5)]
+        );
+        assert_eq!(merged.boundaries[1].status, "Transcendent");
+
+        let merged = FrameworkState::merge(a.clone(), b.clone(), MergeStrategy::Average);
+        assert!((merged.boundaries[0].permeability - 0.6).abs() < 1e-12);
+        assert_eq!(merged.boundaries[0].status, "Maintained");
+
+        let merged = FrameworkState::merge(a, b, MergeStrategy::PreferB);
+        assert_eq!(merged.identity, "B");
+        assert_eq!(merged.boundaries[0].permeability, 0.4);
+    }
+
+    #[test]
+    fn test_domain_registry_roundtrip() {
+        let mut registry = DomainRegistry::new();
+        registry.register_domain(DomainFactory::create("CD").unwrap());
+        registry.register_domain(DomainFactory::create("ED").unwrap());
+
+        let registry = registry.with_weighting_factor(5.0);
+
+        let json = serde_json::to_string(&registry).unwrap();
+        assert_eq!(json, r#"{"domains":["CD","ED"],"weighting_factor":5.0}"#);
+
+        let restored: DomainRegistry = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.domain_names(), vec!["CD", "ED"]);
+        assert_eq!(restored.weighting_factor, 5.0);
+
+        // Registries saved as a bare name list get the default factor
+        let restored: DomainRegistry = serde_json::from_str(r#"["CD","ED"]"#).unwrap();
+        assert_eq!(restored.domain_names(), vec!["CD", "ED"]);
+        assert_eq!(restored.weighting_factor, DEFAULT_WEIGHTING_FACTOR);
+
+        // Unknown domain names are skipped rather than failing the load
+        let restored: DomainRegistry = serde_json::from_str(r#"["CD","Unknown"]"#).unwrap();
+        assert_eq!(restored.domain_names(), vec!["CD"]);
+    }
+
     #[test]
     fn test_prompt_structure() {
         let domain_registry = DomainRegistry::new();
         // Register domains here...
 
-        let framework_state = FrameworkState {
+        let framework_state = FrameworkState::new(
             domain_registry,
-            boundaries: vec![
+            vec![
                 BoundaryState::new("CD-SD".to_string(), 0.8, "Active".to_string()),
                 BoundaryState::new("SD-CuD".to_string(), 0.5, "Active".to_string()),
             ],
-            identity: "User Identity".to_string(),
-        };
+            "User Identity".to_string(),
+        );
 
         let prompt_engine = PromptEngine::new(framework_state);
         let user_input = "Hello, world!";
@@ -535,6 +1587,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resonance_edge_cases() {
+        let boundary = |frequency: f64, amplitude: f64, phase: f64| {
+            BoundaryState::with_oscillation(
+                "b".to_string(),
+                0.5,
+                "Maintained".to_string(),
+                frequency,
+                amplitude,
+                phase,
+            )
+        };
+
+        // Identical boundaries resonate
+        assert!(boundary(1.0, 0.2, 0.0).resonates_with(&boundary(1.0, 0.2, 0.0)));
+
+        // 3x frequency difference does not
+        assert!(!boundary(1.0, 0.2, 0.0).resonates_with(&boundary(3.0, 0.2, 0.0)));
+
+        // Phase opposition does not, and phases wrap around 2π
+        assert!(!boundary(1.0, 0.2, 0.0).resonates_with(&boundary(1.0, 0.2, PI)));
+        assert!(boundary(1.0, 0.2, 0.05).resonates_with(&boundary(1.0, 0.2, 2.0 * PI - 0.05)));
+
+        // Static boundaries match each other but not oscillating ones
+        assert!(boundary(0.0, 0.2, 0.0).resonates_with(&boundary(0.0, 0.2, 0.0)));
+        assert!(!boundary(0.0, 0.2, 0.0).resonates_with(&boundary(1.0, 0.2, 0.0)));
+
+        // Custom thresholds
+        let strict = ResonanceThresholds {
+            frequency_tolerance: 0.01,
+            phase_tolerance: PI / 4.0,
+            min_amplitude: 0.1,
+        };
+        let base = boundary(1.0, 0.2, 0.0);
+        assert!(base.resonates_with_thresholds(&boundary(1.0, 0.2, 0.5), &strict));
+        assert!(!base.resonates_with_thresholds(&boundary(1.05, 0.2, 0.0), &strict));
+        assert!(!base.resonates_with_thresholds(&boundary(1.0, 0.05, 0.0), &strict));
+    }
+
     #[test]
     fn test_boundary_resonance_strength() {
         // Test resonance strength calculation (0.0-1.0)
@@ -639,7 +1730,7 @@ mod tests {
     #[test]
     fn test_resonance_cascade_multi_boundary() {
         // Test that 3+ boundaries can synchronize if they have compatible parameters
-        let boundaries = vec![
+        let boundaries = [
             BoundaryState::with_oscillation(
                 "b1".to_string(),
                 0.5,
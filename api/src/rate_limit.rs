@@ -0,0 +1,294 @@
+// Provider Rate Limit Tracking
+// Reads rate-limit headers from provider responses and throttles proactively
+
+use crate::llm_error::LlmError;
+use crate::LlmProvider;
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Rate-limit state reported by the provider on its most recent response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitInfo {
+    pub requests_remaining: Option<u32>,
+    pub tokens_remaining: Option<u32>,
+    pub reset_at: Option<DateTime<Utc>>,
+}
+
+impl RateLimitInfo {
+    /// Parse OpenAI's `x-ratelimit-*` headers. Reset times are relative
+    /// durations such as "1s", "6m0s" or "20ms".
+    pub fn from_openai_headers(headers: &HeaderMap) -> Option<Self> {
+        let reset_at = header_str(headers, "x-ratelimit-reset-requests")
+            .and_then(parse_reset_duration)
+            .and_then(|duration| chrono::Duration::from_std(duration).ok())
+            .map(|duration| Utc::now() + duration);
+
+        Self::from_parts(
+            header_u32(headers, "x-ratelimit-remaining-requests"),
+            header_u32(headers, "x-ratelimit-remaining-tokens"),
+            reset_at,
+        )
+    }
+
+    /// Parse Anthropic's `anthropic-ratelimit-*` headers. Reset times are RFC 3339.
+    pub fn from_anthropic_headers(headers: &HeaderMap) -> Option<Self> {
+        let reset_at = header_str(headers, "anthropic-ratelimit-requests-reset")
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            .map(|reset| reset.with_timezone(&Utc));
+
+        Self::from_parts(
+            header_u32(headers, "anthropic-ratelimit-requests-remaining"),
+            header_u32(headers, "anthropic-ratelimit-tokens-remaining"),
+            reset_at,
+        )
+    }
+
+    fn from_parts(
+        requests_remaining: Option<u32>,
+        tokens_remaining: Option<u32>,
+        reset_at: Option<DateTime<Utc>>,
+    ) -> Option<Self> {
+        if requests_remaining.is_none() && tokens_remaining.is_none() && reset_at.is_none() {
+            return None;
+        }
+        Some(Self {
+            requests_remaining,
+            tokens_remaining,
+            reset_at,
+        })
+    }
+
+    /// How long to wait before the next request, if the request quota is exhausted
+    pub fn wait_time(&self) -> Option<Duration> {
+        if self.requests_remaining != Some(0) {
+            return None;
+        }
+        self.reset_at
+            .and_then(|reset_at| (reset_at - Utc::now()).to_std().ok())
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn header_u32(headers: &HeaderMap, name: &str) -> Option<u32> {
+    header_str(headers, name).and_then(|value| value.trim().parse().ok())
+}
+
+/// Parse Go-style durations like "1h2m3s", "6m0s", "1.5s" or "20ms"
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_end].parse().ok()?;
+        rest = &rest[number_end..];
+
+        let (seconds_per_unit, unit_len) = if rest.starts_with("ms") {
+            (0.001, 2)
+        } else if rest.starts_with('h') {
+            (3600.0, 1)
+        } else if rest.starts_with('m') {
+            (60.0, 1)
+        } else if rest.starts_with('s') {
+            (1.0, 1)
+        } else {
+            return None;
+        };
+        total += number * seconds_per_unit;
+        rest = &rest[unit_len..];
+    }
+
+    Some(Duration::from_secs_f64(total))
+}
+
+/// Wraps a provider and sleeps until the reported reset time when the
+/// provider's last response said no requests remain
+pub struct RateLimitGuard {
+    inner: Box<dyn LlmProvider>,
+    max_wait: Duration,
+}
+
+impl RateLimitGuard {
+    pub fn new(inner: Box<dyn LlmProvider>) -> Self {
+        Self {
+            inner,
+            max_wait: Duration::from_secs(60),
+        }
+    }
+
+    /// Cap how long a single request may be held back
+    pub fn set_max_wait(&mut self, max_wait: Duration) {
+        self.max_wait = max_wait;
+    }
+
+    async fn wait_for_quota(&self) {
+        if let Some(wait) = self
+            .inner
+            .last_rate_limit_info()
+            .and_then(|info| info.wait_time())
+        {
+            tokio::time::sleep(wait.min(self.max_wait)).await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for RateLimitGuard {
+    fn get_api_key(&self) -> String {
+        self.inner.get_api_key()
+    }
+
+    fn get_provider_name(&self) -> String {
+        self.inner.get_provider_name()
+    }
+
+    fn get_model_name(&self) -> String {
+        self.inner.get_model_name()
+    }
+
+    async fn send_request(&self, prompt: &str) -> Result<String, LlmError> {
+        self.wait_for_quota().await;
+        self.inner.send_request(prompt).await
+    }
+
+    async fn send_with_system_prompt(&self, system: &str, user: &str) -> Result<String, LlmError> {
+        self.wait_for_quota().await;
+        self.inner.send_with_system_prompt(system, user).await
+    }
+
+    fn estimate_cost(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        self.inner.estimate_cost(input_tokens, output_tokens)
+    }
+
+    fn is_known_model(&self) -> bool {
+        self.inner.is_known_model()
+    }
+
+    fn last_rate_limit_info(&self) -> Option<RateLimitInfo> {
+        self.inner.last_rate_limit_info()
+    }
+
+    async fn validate_credentials(&self) -> Result<(), LlmError> {
+        self.inner.validate_credentials().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OpenAiLlm;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parse_reset_duration() {
+        assert_eq!(parse_reset_duration("1s"), Some(Duration::from_secs(1)));
+        assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(
+            parse_reset_duration("20ms"),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            parse_reset_duration("1.5s"),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(parse_reset_duration("soon"), None);
+        assert_eq!(parse_reset_duration(""), None);
+    }
+
+    #[test]
+    fn test_anthropic_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "anthropic-ratelimit-requests-remaining",
+            "42".parse().unwrap(),
+        );
+        headers.insert(
+            "anthropic-ratelimit-requests-reset",
+            "2030-01-01T00:00:00Z".parse().unwrap(),
+        );
+
+        let info = RateLimitInfo::from_anthropic_headers(&headers).unwrap();
+        assert_eq!(info.requests_remaining, Some(42));
+        assert_eq!(info.tokens_remaining, None);
+        assert_eq!(
+            info.reset_at.unwrap().to_rfc3339(),
+            "2030-01-01T00:00:00+00:00"
+        );
+        assert!(info.wait_time().is_none(), "requests remain");
+
+        assert!(RateLimitInfo::from_anthropic_headers(&HeaderMap::new()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_guard_waits_for_reset_when_quota_exhausted() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-remaining-requests", "0")
+                    .insert_header("x-ratelimit-remaining-tokens", "1000")
+                    .insert_header("x-ratelimit-reset-requests", "1s")
+                    .set_body_json(serde_json::json!({
+                        "choices": [{"message": {"content": "Hello"}}]
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        let mut provider = OpenAiLlm::new("test-key".to_string(), "gpt-4o".to_string());
+        provider.set_base_url(&server.uri());
+        let guard = RateLimitGuard::new(Box::new(provider));
+        assert!(guard.last_rate_limit_info().is_none());
+
+        // First request goes straight through and records the exhausted quota
+        let started = std::time::Instant::now();
+        guard.send_with_system_prompt("system", "hi").await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        let info = guard.last_rate_limit_info().unwrap();
+        assert_eq!(info.requests_remaining, Some(0));
+        assert_eq!(info.tokens_remaining, Some(1000));
+
+        // Second request waits for the reset window
+        let started = std::time::Instant::now();
+        guard.send_with_system_prompt("system", "hi").await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(800));
+    }
+
+    #[tokio::test]
+    async fn test_guard_forwards_model_check_and_validation() {
+        let unknown = RateLimitGuard::new(Box::new(OpenAiLlm::new(
+            "test-key".to_string(),
+            "gpt-9-turbo".to_string(),
+        )));
+        assert!(!unknown.is_known_model());
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(serde_json::json!({"max_tokens": 1})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"content": "Hi"}}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mut provider = OpenAiLlm::new("test-key".to_string(), "gpt-4o".to_string());
+        provider.set_base_url(&server.uri());
+        let guard = RateLimitGuard::new(Box::new(provider));
+        assert!(guard.is_known_model());
+        guard.validate_credentials().await.unwrap();
+    }
+}
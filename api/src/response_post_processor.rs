@@ -0,0 +1,146 @@
+// Response Post-Processing
+// Cleans LLM output before it is returned and stored
+
+const VIF_CONTEXT_OPEN: &str = "<vif_context>";
+const VIF_CONTEXT_CLOSE: &str = "</vif_context>";
+const CODE_FENCE: &str = "```";
+
+/// Sanitizes LLM responses that echo framework markup or break Markdown
+pub struct ResponsePostProcessor;
+
+impl ResponsePostProcessor {
+    /// Strip echoed VIF context, normalize whitespace and close code fences
+    pub fn clean(response: &str) -> String {
+        let stripped = Self::strip_vif_context(response);
+        let normalized = Self::normalize_whitespace(&stripped);
+        Self::close_code_fences(&normalized)
+    }
+
+    /// Remove `<vif_context>` blocks, and any stray open or close tags,
+    /// that a confused model copied from the prompt
+    pub fn strip_vif_context(response: &str) -> String {
+        let mut cleaned = response.to_string();
+        loop {
+            // ASCII lowercasing keeps byte offsets aligned with the original text
+            let lowered = cleaned.to_ascii_lowercase();
+            let Some(start) = lowered.find(VIF_CONTEXT_OPEN) else {
+                break;
+            };
+            let end = lowered[start..]
+                .find(VIF_CONTEXT_CLOSE)
+                .map(|offset| start + offset + VIF_CONTEXT_CLOSE.len())
+                .unwrap_or(start + VIF_CONTEXT_OPEN.len());
+            cleaned.replace_range(start..end, "");
+        }
+
+        while let Some(start) = cleaned.to_ascii_lowercase().find(VIF_CONTEXT_CLOSE) {
+            cleaned.replace_range(start..start + VIF_CONTEXT_CLOSE.len(), "");
+        }
+        cleaned
+    }
+
+    /// Trim trailing spaces on each line, collapse runs of blank lines
+    /// to one and trim the response as a whole. Lines inside fenced code
+    /// blocks are left as they are.
+    pub fn normalize_whitespace(response: &str) -> String {
+        let mut lines: Vec<&str> = Vec::new();
+        let mut in_code_block = false;
+        for line in response.lines() {
+            if line.trim_start().starts_with(CODE_FENCE) {
+                in_code_block = !in_code_block;
+                lines.push(line.trim_end());
+                continue;
+            }
+            if in_code_block {
+                lines.push(line);
+                continue;
+            }
+            let line = line.trim_end();
+            if line.is_empty() && lines.last().is_some_and(|last| last.is_empty()) {
+                continue;
+            }
+            lines.push(line);
+        }
+        lines.join("\n").trim().to_string()
+    }
+
+    /// Append a closing fence when the response leaves a code block open
+    pub fn close_code_fences(response: &str) -> String {
+        let fences = response
+            .lines()
+            .filter(|line| line.trim_start().starts_with(CODE_FENCE))
+            .count();
+        if fences % 2 == 0 {
+            response.to_string()
+        } else {
+            format!("{}\n{}", response, CODE_FENCE)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_vif_context() {
+        let echoed = "<VIF_CONTEXT><domains/></vif_context>Here is my answer.";
+        assert_eq!(
+            ResponsePostProcessor::strip_vif_context(echoed),
+            "Here is my answer."
+        );
+
+        let stray = "Answer <vif_context> with </vif_context> tags";
+        assert_eq!(
+            ResponsePostProcessor::strip_vif_context("Answer </vif_context> here"),
+            "Answer  here"
+        );
+        assert_eq!(
+            ResponsePostProcessor::strip_vif_context(stray),
+            "Answer  tags"
+        );
+        assert_eq!(
+            ResponsePostProcessor::strip_vif_context("Unclosed <vif_context>tail"),
+            "Unclosed tail"
+        );
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        let messy = "\n\nFirst line   \n\n\n\nSecond line\t\n\n";
+        assert_eq!(
+            ResponsePostProcessor::normalize_whitespace(messy),
+            "First line\n\nSecond line"
+        );
+
+        // Code keeps its blank lines and indentation
+        let code =
+            "Example:\n\n\n```python\ndef a():\n    pass\n\n\ndef b():  \n    pass\n```\n\n\nDone";
+        assert_eq!(
+            ResponsePostProcessor::normalize_whitespace(code),
+            "Example:\n\n```python\ndef a():\n    pass\n\n\ndef b():  \n    pass\n```\n\nDone"
+        );
+    }
+
+    #[test]
+    fn test_close_code_fences() {
+        let open = "Example:\n```rust\nfn main() {}";
+        assert_eq!(
+            ResponsePostProcessor::close_code_fences(open),
+            "Example:\n```rust\nfn main() {}\n```"
+        );
+
+        let closed = "```\ncode\n```";
+        assert_eq!(ResponsePostProcessor::close_code_fences(closed), closed);
+    }
+
+    #[test]
+    fn test_clean_combines_all_steps() {
+        let response =
+            "<vif_context>leaked</vif_context>\n\n\nAnswer:   \n```python\nprint('hi')\n\n\n";
+        assert_eq!(
+            ResponsePostProcessor::clean(response),
+            "Answer:\n```python\nprint('hi')\n```"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+// Response Scoring
+// Post-hoc heuristics for how well a response fits the flow that produced it
+
+use crate::flow_process::FlowContext;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Word stems characteristic of each domain, matched as word prefixes
+const DOMAIN_VOCABULARY: &[(&str, &[&str])] = &[
+    (
+        "CD",
+        &[
+            "algorithm",
+            "code",
+            "comput",
+            "data",
+            "efficien",
+            "function",
+            "logic",
+            "program",
+            "complexity",
+            "optimi",
+        ],
+    ),
+    (
+        "SD",
+        &[
+            "evidence",
+            "experiment",
+            "hypothes",
+            "measur",
+            "observ",
+            "research",
+            "theor",
+            "empiric",
+            "predict",
+            "scien",
+        ],
+    ),
+    (
+        "CuD",
+        &[
+            "cultur",
+            "societ",
+            "social",
+            "tradition",
+            "histor",
+            "communit",
+            "value",
+            "meaning",
+            "narrative",
+            "people",
+        ],
+    ),
+    (
+        "ED",
+        &[
+            "feel",
+            "felt",
+            "experienc",
+            "sens",
+            "aware",
+            "emotion",
+            "perceiv",
+            "intuiti",
+            "personal",
+            "embodi",
+        ],
+    ),
+];
+
+/// Vocabulary hits at which a domain counts as fully addressed
+const ALIGNMENT_TARGET_HITS: usize = 3;
+
+/// Word count at which a response stops gaining credit for length
+const COMPLEXITY_TARGET_WORDS: usize = 100;
+
+/// Heuristic quality scores for a response, each in [0.0, 1.0]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ResponseScore {
+    /// How well the response vocabulary covers the active domains,
+    /// weighted by activation
+    pub domain_alignment: f64,
+    /// How many distinct domains the response draws on
+    pub boundary_traversal: f64,
+    /// Lexical variety and length as a rough proxy for complexity
+    pub quality_emergence: f64,
+    pub overall: f64,
+}
+
+/// Scores LLM responses against the flow context they answered
+pub struct ResponseScorer;
+
+impl ResponseScorer {
+    pub fn score(response: &str, context: &FlowContext) -> ResponseScore {
+        let activations: HashMap<String, f64> = context
+            .domains
+            .iter()
+            .map(|(name, domain)| (name.clone(), domain.activation))
+            .collect();
+        Self::score_activations(response, &activations)
+    }
+
+    /// Score against domain activations keyed by domain name, e.g. those
+    /// stored in a state snapshot
+    pub fn score_activations(
+        response: &str,
+        domain_activations: &HashMap<String, f64>,
+    ) -> ResponseScore {
+        let words = Self::words(response);
+
+        let domain_alignment = Self::domain_alignment(&words, domain_activations);
+        let boundary_traversal = Self::boundary_traversal(&words);
+        let quality_emergence = Self::quality_emergence(&words);
+
+        ResponseScore {
+            domain_alignment,
+            boundary_traversal,
+            quality_emergence,
+            overall: (domain_alignment + boundary_traversal + quality_emergence) / 3.0,
+        }
+    }
+
+    fn words(response: &str) -> Vec<String> {
+        response
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    }
+
+    /// Number of words in the response matching a domain's vocabulary
+    fn vocabulary_hits(words: &[String], stems: &[&str]) -> usize {
+        words
+            .iter()
+            .filter(|word| stems.iter().any(|stem| word.starts_with(stem)))
+            .count()
+    }
+
+    fn domain_alignment(words: &[String], domain_activations: &HashMap<String, f64>) -> f64 {
+        let mut weighted = 0.0;
+        let mut total_activation = 0.0;
+        for (name, stems) in DOMAIN_VOCABULARY {
+            let Some(&activation) = domain_activations.get(*name) else {
+                continue;
+            };
+            let coverage = (Self::vocabulary_hits(words, stems) as f64
+                / ALIGNMENT_TARGET_HITS as f64)
+                .min(1.0);
+            weighted += activation * coverage;
+            total_activation += activation;
+        }
+
+        if total_activation > 0.0 {
+            weighted / total_activation
+        } else {
+            0.0
+        }
+    }
+
+    /// 0.0 when the response stays within one domain, 1.0 when it reaches all of them
+    fn boundary_traversal(words: &[String]) -> f64 {
+        let referenced = DOMAIN_VOCABULARY
+            .iter()
+            .filter(|(_, stems)| Self::vocabulary_hits(words, stems) > 0)
+            .count();
+        referenced.saturating_sub(1) as f64 / (DOMAIN_VOCABULARY.len() - 1) as f64
+    }
+
+    fn quality_emergence(words: &[String]) -> f64 {
+        if words.is_empty() {
+            return 0.0;
+        }
+        let unique: HashSet<&String> = words.iter().collect();
+        let variety = unique.len() as f64 / words.len() as f64;
+        let length = (words.len() as f64 / COMPLEXITY_TARGET_WORDS as f64).min(1.0);
+        (variety + length) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_process::DomainActivation;
+    use crate::prompt_engine::{DomainRegistry, FrameworkState};
+
+    fn context_with_domains(domains: &[(&str, f64)]) -> FlowContext {
+        let mut context = FlowContext::new(
+            "How should I design this system?".to_string(),
+            0.5,
+            FrameworkState::new(DomainRegistry::new(), vec![], "Test Identity".to_string()),
+        );
+        for (name, activation) in domains {
+            context.domains.insert(
+                name.to_string(),
+                DomainActivation {
+                    activation: *activation,
+                },
+            );
+        }
+        context
+    }
+
+    #[test]
+    fn test_multi_domain_response_traverses_more_boundaries() {
+        let context = context_with_domains(&[("CD", 0.9), ("SD", 0.6), ("ED", 0.4)]);
+
+        let single = ResponseScorer::score(
+            "Use an efficient algorithm: the function sorts the data in logarithmic time.",
+            &context,
+        );
+        let multi = ResponseScorer::score(
+            "An efficient algorithm helps, but measure it: run an experiment to test the \
+             hypothesis, and notice how the design feels to the people who use it.",
+            &context,
+        );
+
+        assert_eq!(single.boundary_traversal, 0.0);
+        assert!(multi.boundary_traversal > single.boundary_traversal);
+        assert!(multi.domain_alignment > single.domain_alignment);
+        for score in [single, multi] {
+            for value in [
+                score.domain_alignment,
+                score.boundary_traversal,
+                score.quality_emergence,
+                score.overall,
+            ] {
+                assert!((0.0..=1.0).contains(&value), "{:?}", score);
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_response_scores_zero() {
+        let context = context_with_domains(&[("CD", 0.9)]);
+        let score = ResponseScorer::score("", &context);
+        assert_eq!(score.overall, 0.0);
+
+        // Without active domains nothing can align
+        let score = ResponseScorer::score("An efficient algorithm", &context_with_domains(&[]));
+        assert_eq!(score.domain_alignment, 0.0);
+    }
+}
@@ -1,6 +1,7 @@
 //! Test utilities for setting up in-memory database and test fixtures
 
 use sqlx::SqlitePool;
+use uuid::Uuid;
 
 /// Creates an in-memory SQLite database with all migrations applied
 pub async fn setup_test_db() -> Result<SqlitePool, sqlx::Error> {
@@ -13,6 +14,22 @@ pub async fn setup_test_db() -> Result<SqlitePool, sqlx::Error> {
     Ok(pool)
 }
 
+/// Inserts a user row with placeholder account details
+pub async fn insert_test_user(pool: &SqlitePool, user_id: Uuid) {
+    sqlx::query(
+        "INSERT INTO users (id, provider, provider_id, email, name, created_at, last_login)
+         VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))",
+    )
+    .bind(user_id.as_bytes().to_vec())
+    .bind("test")
+    .bind(format!("test-account-{}", user_id))
+    .bind("test@example.com")
+    .bind("Test User")
+    .execute(pool)
+    .await
+    .expect("Should create test user");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,6 +2,11 @@
 
 use crate::memory::CompactStateSnapshot;
 
+/// Measures prompt text in tokens
+pub trait TokenCounter {
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
 pub struct TokenOptimizer {
     token_budget: usize,
 }
@@ -11,6 +16,11 @@ impl TokenOptimizer {
         Self { token_budget }
     }
 
+    /// Tokens a prompt's framework context may take up
+    pub fn token_budget(&self) -> usize {
+        self.token_budget
+    }
+
     pub fn optimize(&self, compact_state_snapshot: &CompactStateSnapshot) -> String {
         let mut context = String::new();
         let mut used_tokens = 0;
@@ -74,12 +84,18 @@ impl TokenOptimizer {
         interfaces
     }
 
-    fn count_tokens(&self, text: &str) -> usize {
+    pub(crate) fn count_tokens(&self, text: &str) -> usize {
         // Simple token counting implementation
         text.split_whitespace().count()
     }
 }
 
+impl TokenCounter for TokenOptimizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        TokenOptimizer::count_tokens(self, text)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,7 +108,10 @@ mod tests {
 
         // Use in-memory database for testing
         let db_pool = setup_test_db().await.unwrap();
-        let memory_manager = crate::memory::MemoryManager { db_pool };
+        let memory_manager = crate::memory::MemoryManager {
+            db_pool,
+            organization_id: None,
+        };
 
         // Create a test user first (required by foreign key constraint)
         let user_id = uuid::Uuid::new_v4();
@@ -129,7 +148,7 @@ mod tests {
 
         let user_input = "Sample query for token optimization testing";
         memory_manager
-            .create_snapshot(domains, boundaries, patterns, user_id, user_input)
+            .create_snapshot(domains, boundaries, patterns, user_id, user_input, None)
             .await
             .unwrap();
         let compact_state_snapshot = memory_manager
@@ -0,0 +1,127 @@
+// Per-User Rate Limiting
+// Token buckets that stop one user from monopolizing the shared provider
+
+use crate::llm_error::LlmError;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use uuid::Uuid;
+
+/// Per-user request and token allowances, refilled continuously over a minute
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimiterConfig {
+    pub requests_per_minute: u32,
+    pub tokens_per_minute: u32,
+}
+
+/// Remaining allowance for one user
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    requests: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+pub struct RateLimiter {
+    config: RateLimiterConfig,
+    buckets: DashMap<Uuid, TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimiterConfig) -> Self {
+        Self {
+            config,
+            buckets: DashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> RateLimiterConfig {
+        self.config
+    }
+
+    /// Take one request and `tokens` input tokens from the user's allowance.
+    /// Requests larger than the whole per-minute token budget are charged the
+    /// full budget rather than being rejected forever.
+    pub fn check(&self, user_id: Uuid, tokens: u32) -> Result<(), LlmError> {
+        let requests_capacity = self.config.requests_per_minute as f64;
+        let tokens_capacity = self.config.tokens_per_minute as f64;
+        let now = Instant::now();
+
+        let mut bucket = self.buckets.entry(user_id).or_insert_with(|| TokenBucket {
+            requests: requests_capacity,
+            tokens: tokens_capacity,
+            last_refill: now,
+        });
+
+        let elapsed_minutes = now.duration_since(bucket.last_refill).as_secs_f64() / 60.0;
+        bucket.requests =
+            (bucket.requests + elapsed_minutes * requests_capacity).min(requests_capacity);
+        bucket.tokens = (bucket.tokens + elapsed_minutes * tokens_capacity).min(tokens_capacity);
+        bucket.last_refill = now;
+
+        let token_cost = (tokens as f64).min(tokens_capacity);
+        let retry_after_secs = retry_after_secs(1.0 - bucket.requests, requests_capacity).max(
+            retry_after_secs(token_cost - bucket.tokens, tokens_capacity),
+        );
+        if retry_after_secs > 0 {
+            return Err(LlmError::UserRateLimited { retry_after_secs });
+        }
+
+        bucket.requests -= 1.0;
+        bucket.tokens -= token_cost;
+        Ok(())
+    }
+}
+
+/// Whole seconds until `deficit` units refill at `per_minute`, or 0 if none are missing
+fn retry_after_secs(deficit: f64, per_minute: f64) -> u64 {
+    if deficit <= 0.0 {
+        0
+    } else if per_minute <= 0.0 {
+        60
+    } else {
+        (deficit / per_minute * 60.0).ceil().max(1.0) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_limit_per_user() {
+        let limiter = RateLimiter::new(RateLimiterConfig {
+            requests_per_minute: 2,
+            tokens_per_minute: 10_000,
+        });
+        let user_id = Uuid::new_v4();
+
+        assert!(limiter.check(user_id, 10).is_ok());
+        assert!(limiter.check(user_id, 10).is_ok());
+        match limiter.check(user_id, 10) {
+            Err(LlmError::UserRateLimited { retry_after_secs }) => {
+                // One request refills every 30 seconds
+                assert!((1..=30).contains(&retry_after_secs));
+            }
+            other => panic!("Expected UserRateLimited, got {:?}", other),
+        }
+
+        // Other users keep their own allowance
+        assert!(limiter.check(Uuid::new_v4(), 10).is_ok());
+    }
+
+    #[test]
+    fn test_token_limit_per_user() {
+        let limiter = RateLimiter::new(RateLimiterConfig {
+            requests_per_minute: 100,
+            tokens_per_minute: 100,
+        });
+        let user_id = Uuid::new_v4();
+
+        assert!(limiter.check(user_id, 80).is_ok());
+        assert!(matches!(
+            limiter.check(user_id, 80),
+            Err(LlmError::UserRateLimited { .. })
+        ));
+    }
+}